@@ -26,7 +26,7 @@ fn main() {
             Err(_) => 1
         });
     } else {
-        ::std::process::exit(match StormParser::parse_replay(&replay_file) {
+        ::std::process::exit(match StormParser::parse_replay_json(&replay_file) {
             Ok(result) => {
                 println!("{}", result);
                 0
@@ -1,11 +1,31 @@
+//! A Blizzard StormReplay parser.
+//!
+//! `StormParser` is the entry point: it opens a `.StormReplay` archive and decodes it into a
+//! `StormReplay`, which holds the replay metadata, the players and the typed game, message and
+//! tracker event streams. Replays can be read from a path on disk, from an in-memory buffer, or from any
+//! `Read + Seek` source.
+//!
+//! Replays are treated as untrusted input: anything malformed or unexpected in them is reported as a
+//! `ReplayError`, and never causes a panic.
+//!
+//! ```no_run
+//! use storm_parser::StormParser;
+//!
+//! let replay = StormParser::parse_replay("match.StormReplay").unwrap();
+//! for player in &replay.players {
+//!     println!("{} played {}", player.name, player.character);
+//! }
+//! ```
 #![feature(alloc_system)]
 extern crate alloc_system;
 
 extern crate backtrace;
 extern crate byteorder;
+extern crate bzip2;
 extern crate chrono;
 #[macro_use]
 extern crate enum_primitive_derive;
+extern crate flate2;
 extern crate hex_slice;
 extern crate md5;
 extern crate mpq;
@@ -24,4 +44,4 @@ extern crate unicode_reverse;
 extern crate uuid;
 
 mod storm_parser;
-pub use storm_parser::StormParser;
+pub use storm_parser::*;
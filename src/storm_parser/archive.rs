@@ -0,0 +1,347 @@
+use std::cmp;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use bzip2::read::BzDecoder;
+use flate2::read::ZlibDecoder;
+use mpq::Archive;
+
+use storm_parser::primitives::*;
+
+const USER_DATA_MAGIC: &'static [u8; 4] = b"MPQ\x1B";
+const HEADER_MAGIC: &'static [u8; 4] = b"MPQ\x1A";
+
+const HASH_TYPE_TABLE_OFFSET: u32 = 0;
+const HASH_TYPE_NAME_A: u32 = 1;
+const HASH_TYPE_NAME_B: u32 = 2;
+const HASH_TYPE_FILE_KEY: u32 = 3;
+
+const FILE_IMPLODE: u32 = 0x00000100;
+const FILE_COMPRESS: u32 = 0x00000200;
+const FILE_ENCRYPTED: u32 = 0x00010000;
+const FILE_SINGLE_UNIT: u32 = 0x01000000;
+const FILE_SECTOR_CRC: u32 = 0x04000000;
+const FILE_EXISTS: u32 = 0x80000000;
+
+const COMPRESSION_ZLIB: u8 = 0x02;
+const COMPRESSION_BZIP2: u8 = 0x10;
+
+/// A source of replay sections.
+///
+/// This is implemented for `mpq::Archive`, for replays opened from disk, and for `StreamArchive`, for
+/// replays held in memory or read from any other seekable source.
+pub trait ReplayArchive {
+    /// Reads the archive user data, which holds the replay header.
+    fn read_user_data(&mut self) -> ReplayResult<Option<Vec<u8>>>;
+
+    /// Reads the full contents of the named file in the archive.
+    fn read_file(&mut self, name: &str) -> ReplayResult<Vec<u8>>;
+}
+
+impl ReplayArchive for Archive {
+    fn read_user_data(&mut self) -> ReplayResult<Option<Vec<u8>>> {
+        Archive::read_user_data(self)
+            .map_err(|_| ReplayError::new(ReplayErrorKind::FileError, "failed to read replay detail; is something wrong with the file permissions? *shrug*"))
+    }
+
+    fn read_file(&mut self, name: &str) -> ReplayResult<Vec<u8>> {
+        match self.open_file(name) {
+            Ok(file) => {
+                let mut buf: Vec<u8> = vec![0; file.size() as usize];
+                match file.read(self, &mut buf) {
+                    Ok(_) => Ok(buf),
+                    Err(_) => Err(ReplayError::new(ReplayErrorKind::ArchiveError, &format!("failed to read {}", name)))
+                }
+            },
+            Err(_) => Err(ReplayError::new(ReplayErrorKind::ArchiveError, &format!("failed to open {}", name)))
+        }
+    }
+}
+
+struct HashEntry {
+    name_a: u32,
+    name_b: u32,
+    block_index: u32,
+}
+
+struct BlockEntry {
+    offset: u32,
+    archived_size: u32,
+    size: u32,
+    flags: u32,
+}
+
+/// An MPQ archive read from any `Read + Seek` source, such as a `Cursor` over an in-memory buffer.
+///
+/// Only the subset of the MPQ format used by replays is supported: unencrypted files compressed with
+/// zlib or bzip2.
+pub struct StreamArchive<R: Read + Seek> {
+    inner: R,
+    crypt_table: Vec<u32>,
+    header_offset: u64,
+    sector_size: u32,
+    user_data: Option<Vec<u8>>,
+    hash_table: Vec<HashEntry>,
+    block_table: Vec<BlockEntry>,
+}
+
+impl<'a> StreamArchive<Cursor<&'a [u8]>> {
+    /// Opens an archive held in an in-memory buffer.
+    pub fn from_bytes(buf: &'a [u8]) -> ReplayResult<StreamArchive<Cursor<&'a [u8]>>> {
+        StreamArchive::new(Cursor::new(buf))
+    }
+}
+
+impl<R: Read + Seek> StreamArchive<R> {
+    /// Opens an archive from the given source, reading its header and file tables.
+    pub fn new(mut inner: R) -> ReplayResult<StreamArchive<R>> {
+        let crypt_table = build_crypt_table();
+
+        // Replays are prefixed with a user data block, which holds the replay header, and which points
+        // us to where the actual archive header lives.
+        let mut magic = [0u8; 4];
+        inner.seek(SeekFrom::Start(0))?;
+        inner.read_exact(&mut magic)?;
+
+        let mut header_offset = 0u64;
+        let mut user_data = None;
+        if &magic == USER_DATA_MAGIC {
+            inner.read_u32::<LittleEndian>()?; // user data size
+            header_offset = inner.read_u32::<LittleEndian>()? as u64;
+            let user_data_header_size = inner.read_u32::<LittleEndian>()?;
+
+            user_data = Some(read_len(&mut inner, user_data_header_size)?);
+        }
+
+        inner.seek(SeekFrom::Start(header_offset))?;
+        inner.read_exact(&mut magic)?;
+        if &magic != HEADER_MAGIC {
+            return Err(ReplayError::new(ReplayErrorKind::ArchiveError, "no archive header found; is this a replay file?"));
+        }
+
+        inner.read_u32::<LittleEndian>()?; // header size
+        inner.read_u32::<LittleEndian>()?; // archive size
+        inner.read_u16::<LittleEndian>()?; // format version
+        let sector_size_shift = inner.read_u16::<LittleEndian>()?;
+        let hash_table_offset = inner.read_u32::<LittleEndian>()?;
+        let block_table_offset = inner.read_u32::<LittleEndian>()?;
+        let hash_table_entries = inner.read_u32::<LittleEndian>()?;
+        let block_table_entries = inner.read_u32::<LittleEndian>()?;
+
+        if sector_size_shift > 22 {
+            return Err(ReplayError::new(ReplayErrorKind::ArchiveError, "invalid archive sector size"));
+        }
+
+        let hash_table_key = hash_string(&crypt_table, "(hash table)", HASH_TYPE_FILE_KEY);
+        let hash_table = read_table(&mut inner, &crypt_table, header_offset + hash_table_offset as u64, hash_table_entries, hash_table_key)?
+            .chunks(4)
+            .map(|x| HashEntry { name_a: x[0], name_b: x[1], block_index: x[3] })
+            .collect();
+
+        let block_table_key = hash_string(&crypt_table, "(block table)", HASH_TYPE_FILE_KEY);
+        let block_table = read_table(&mut inner, &crypt_table, header_offset + block_table_offset as u64, block_table_entries, block_table_key)?
+            .chunks(4)
+            .map(|x| BlockEntry { offset: x[0], archived_size: x[1], size: x[2], flags: x[3] })
+            .collect();
+
+        Ok(StreamArchive {
+            inner: inner,
+            crypt_table: crypt_table,
+            header_offset: header_offset,
+            sector_size: 512 << sector_size_shift,
+            user_data: user_data,
+            hash_table: hash_table,
+            block_table: block_table,
+        })
+    }
+
+    fn find_block(&self, name: &str) -> Option<&BlockEntry> {
+        let name_a = hash_string(&self.crypt_table, name, HASH_TYPE_NAME_A);
+        let name_b = hash_string(&self.crypt_table, name, HASH_TYPE_NAME_B);
+        let table_len = self.hash_table.len();
+        if table_len == 0 {
+            return None;
+        }
+
+        // Entries are placed at their hashed slot, or the next free slot after it, so we probe forward
+        // until we either find the file or hit a slot that has never been used.
+        let start = hash_string(&self.crypt_table, name, HASH_TYPE_TABLE_OFFSET) as usize % table_len;
+        for i in 0..table_len {
+            let entry = &self.hash_table[(start + i) % table_len];
+            if entry.block_index == 0xFFFFFFFF {
+                break;
+            }
+
+            // Deleted files leave their hashes behind with a block index that points nowhere, and a later copy
+            // of the same file may sit further along, so we only stop at a block we can actually use.
+            if entry.name_a == name_a && entry.name_b == name_b {
+                if let Some(block) = self.block_table.get(entry.block_index as usize) {
+                    return Some(block);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl<R: Read + Seek> ReplayArchive for StreamArchive<R> {
+    fn read_user_data(&mut self) -> ReplayResult<Option<Vec<u8>>> {
+        Ok(self.user_data.clone())
+    }
+
+    fn read_file(&mut self, name: &str) -> ReplayResult<Vec<u8>> {
+        let (offset, archived_size, size, flags) = match self.find_block(name) {
+            Some(block) => (block.offset, block.archived_size, block.size, block.flags),
+            None => return Err(ReplayError::new(ReplayErrorKind::ArchiveError, &format!("failed to open {}", name)))
+        };
+
+        if flags & FILE_EXISTS == 0 {
+            return Err(ReplayError::new(ReplayErrorKind::ArchiveError, &format!("failed to open {}", name)));
+        }
+
+        if flags & (FILE_ENCRYPTED | FILE_IMPLODE) != 0 {
+            return Err(ReplayError::new(ReplayErrorKind::ArchiveError, &format!("unsupported storage for {}", name)));
+        }
+
+        self.inner.seek(SeekFrom::Start(self.header_offset + offset as u64))?;
+        let raw = read_len(&mut self.inner, archived_size)?;
+
+        let is_compressed = flags & FILE_COMPRESS != 0;
+        if flags & FILE_SINGLE_UNIT != 0 {
+            return if is_compressed && size > archived_size {
+                decompress(&raw, size as usize, name)
+            } else {
+                Ok(raw)
+            };
+        }
+
+        // Uncompressed files are stored as one contiguous run, with no sector offset table in front of them.
+        if !is_compressed {
+            return Ok(raw);
+        }
+
+        // Compressed multi-sector files start with a table of sector offsets, relative to the start of the file,
+        // with one extra entry marking the end of the last sector.
+        let sector_size = self.sector_size as usize;
+        let sector_count = (size as usize + sector_size - 1) / sector_size;
+        let offset_count = if flags & FILE_SECTOR_CRC != 0 { sector_count + 2 } else { sector_count + 1 };
+        if raw.len() < offset_count * 4 {
+            return Err(ReplayError::new(ReplayErrorKind::ArchiveError, &format!("corrupt sector table for {}", name)));
+        }
+
+        let mut offsets_reader = Cursor::new(&raw[..offset_count * 4]);
+        let mut sector_offsets: Vec<usize> = Vec::with_capacity(offset_count);
+        for _ in 0..offset_count {
+            sector_offsets.push(offsets_reader.read_u32::<LittleEndian>()? as usize);
+        }
+
+        let mut buf: Vec<u8> = Vec::new();
+        for i in 0..sector_count {
+            let (start, end) = (sector_offsets[i], sector_offsets[i + 1]);
+            if start > end || end > raw.len() {
+                return Err(ReplayError::new(ReplayErrorKind::ArchiveError, &format!("corrupt sector table for {}", name)));
+            }
+
+            // Sectors that didn't shrink when compressed are stored as-is.
+            let expected_len = cmp::min(sector_size, (size as usize).saturating_sub(buf.len()));
+            let sector = &raw[start..end];
+            if sector.len() < expected_len {
+                buf.extend(decompress(sector, expected_len, name)?);
+            } else {
+                buf.extend_from_slice(sector);
+            }
+        }
+
+        Ok(buf)
+    }
+}
+
+fn build_crypt_table() -> Vec<u32> {
+    let mut table: Vec<u32> = vec![0; 0x500];
+    let mut seed: u32 = 0x00100001;
+
+    for i in 0..0x100 {
+        let mut index = i;
+        for _ in 0..5 {
+            seed = (seed * 125 + 3) % 0x2AAAAB;
+            let high = (seed & 0xFFFF) << 16;
+            seed = (seed * 125 + 3) % 0x2AAAAB;
+            let low = seed & 0xFFFF;
+
+            table[index] = high | low;
+            index += 0x100;
+        }
+    }
+
+    table
+}
+
+fn hash_string(crypt_table: &[u32], s: &str, hash_type: u32) -> u32 {
+    let mut seed1: u32 = 0x7FED7FED;
+    let mut seed2: u32 = 0xEEEEEEEE;
+
+    for c in s.to_uppercase().bytes() {
+        let c = if c == b'/' { b'\\' } else { c } as u32;
+        let value = crypt_table[((hash_type << 8) + c) as usize];
+        seed1 = value ^ seed1.wrapping_add(seed2);
+        seed2 = c.wrapping_add(seed1).wrapping_add(seed2).wrapping_add(seed2 << 5).wrapping_add(3);
+    }
+
+    seed1
+}
+
+fn read_table<R: Read + Seek>(inner: &mut R, crypt_table: &[u32], offset: u64, entries: u32, key: u32) -> ReplayResult<Vec<u32>> {
+    inner.seek(SeekFrom::Start(offset))?;
+
+    let mut table: Vec<u32> = Vec::new();
+    for _ in 0..(entries as u64 * 4) {
+        table.push(inner.read_u32::<LittleEndian>()?);
+    }
+
+    let mut seed1 = key;
+    let mut seed2: u32 = 0xEEEEEEEE;
+    for value in table.iter_mut() {
+        seed2 = seed2.wrapping_add(crypt_table[0x400 + (seed1 & 0xFF) as usize]);
+        let decrypted = *value ^ seed1.wrapping_add(seed2);
+        seed1 = (!seed1 << 0x15).wrapping_add(0x11111111) | (seed1 >> 0x0B);
+        seed2 = decrypted.wrapping_add(seed2).wrapping_add(seed2 << 5).wrapping_add(3);
+        *value = decrypted;
+    }
+
+    Ok(table)
+}
+
+// Reads exactly `len` bytes.  Lengths come from the archive itself, so rather than allocating them up front, we
+// let the buffer grow with what's actually there.
+fn read_len<R: Read>(inner: &mut R, len: u32) -> ReplayResult<Vec<u8>> {
+    let mut buf: Vec<u8> = Vec::new();
+    inner.take(len as u64).read_to_end(&mut buf)?;
+    if buf.len() != len as usize {
+        return Err(ReplayError::new(ReplayErrorKind::ArchiveError, "archive is truncated"));
+    }
+
+    Ok(buf)
+}
+
+// Decompresses a sector, or a whole single-unit file, which must come out to exactly `expected_len` bytes.  A few
+// bytes of compressed data can inflate to gigabytes, so we stop reading as soon as it's clear there's too much.
+fn decompress(data: &[u8], expected_len: usize, name: &str) -> ReplayResult<Vec<u8>> {
+    if data.is_empty() {
+        return Err(ReplayError::new(ReplayErrorKind::ArchiveError, &format!("empty compressed sector in {}", name)));
+    }
+
+    let limit = expected_len as u64 + 1;
+    let mut buf: Vec<u8> = Vec::new();
+    match data[0] {
+        COMPRESSION_ZLIB => { ZlibDecoder::new(&data[1..]).take(limit).read_to_end(&mut buf)?; },
+        COMPRESSION_BZIP2 => { BzDecoder::new(&data[1..]).take(limit).read_to_end(&mut buf)?; },
+        x => return Err(ReplayError::new(ReplayErrorKind::ArchiveError, &format!("unsupported compression type '{}'", x)))
+    }
+
+    if buf.len() != expected_len {
+        return Err(ReplayError::new(ReplayErrorKind::ArchiveError, &format!("corrupt sector in {}", name)));
+    }
+
+    Ok(buf)
+}
@@ -1,11 +1,12 @@
 use std::ffi::CStr;
 
-use mpq::Archive;
 use num_traits::{FromPrimitive, ToPrimitive};
 use unicode_reverse::reverse_grapheme_clusters_in_place;
 
+use storm_parser::archive::ReplayArchive;
 use storm_parser::replay::StormReplay;
 use storm_parser::binary_reader::BinaryReader;
+use storm_parser::draft::{DraftStep, DraftStepKind};
 use storm_parser::primitives::*;
 
 const EMPTY_STR: [u8; 4] = [0, 0, 0, 0];
@@ -36,7 +37,7 @@ impl ReplayAttribute {
                         Err(_) => None
                     }
                 },
-                Err(e) => panic!("error reading value as C string: {}", e)
+                Err(_) => None
             },
             _ => String::from_utf8(buf).ok()
         };
@@ -45,12 +46,7 @@ impl ReplayAttribute {
     }
 
     pub fn get_value_int(&self) -> Option<u32> {
-        match self.get_value_str() {
-            Some(s) => {
-                s.trim().parse::<u32>().ok()
-            },
-            None => panic!("could not parse value int")
-        }
+        self.get_value_str().and_then(|s| s.trim().parse::<u32>().ok())
     }
 }
 
@@ -58,229 +54,231 @@ pub struct ReplayAttributes {
 }
 
 impl ReplayAttributes {
-    pub fn parse_replay_attributes(replay: &mut StormReplay, archive: &mut Archive) -> ReplayResult<()> {
-        match archive.open_file("replay.attributes.events") {
-            Ok(file) => {
-                let file_size = file.size();
-                let mut file_buf: Vec<u8> = vec![0; file_size as usize];
+    pub fn parse_replay_attributes(replay: &mut StormReplay, archive: &mut ReplayArchive) -> ReplayResult<()> {
+        let file_buf = archive.read_file("replay.attributes.events")?;
+        let mut reader = BinaryReader::new(&file_buf);
 
-                match file.read(archive, file_buf.as_mut()) {
-                    Ok(_) => {
-                        let mut reader = BinaryReader::new(&file_buf);
+        // Skip the header.
+        reader.skip_bytes(5)?;
 
-                        // Skip the header.
-                        reader.skip_bytes(5)?;
+        // Why this is LE, I have no fucking idea. *shrug*
+        let attribute_count = reader.read_u32_le()?;
+        // The count comes straight from the file, so we let the list grow as the attributes are actually read
+        // rather than reserving room for however many it claims.
+        let mut attributes: Vec<ReplayAttribute> = Vec::new();
 
-                        // Why this is LE, I have no fucking idea. *shrug*
-                        let attribute_count = reader.read_u32_le()?;
-                        let mut attributes: Vec<ReplayAttribute> = Vec::with_capacity(attribute_count as usize);
+        for _ in 0..attribute_count {
+            let header = reader.read_u32_le()?;
+            let type_val = ReplayAttributeEventType::from_u32(reader.read_u32_le()?);
+            let player_id = reader.read_u8()? as u32;
 
-                        for _ in 0..attribute_count {
-                            let header = reader.read_u32_le()?;
-                            let type_val = ReplayAttributeEventType::from_u32(reader.read_u32_le()?);
-                            let player_id = reader.read_u8()? as u32;
+            let mut attribute = ReplayAttribute {
+                header: header,
+                attribute_type: type_val,
+                player_id: player_id,
+                value: [0u8; 4],
+            };
 
-                            let mut attribute = ReplayAttribute {
-                                header: header,
-                                attribute_type: type_val,
-                                player_id: player_id,
-                                value: [0u8; 4],
-                            };
+            reader.read_bytes_direct(&mut attribute.value)?;
 
-                            reader.read_bytes_direct(&mut attribute.value)?;
-
-                            attributes.push(attribute);
-                        }
+            attributes.push(attribute);
+        }
 
-                        // Filter out unknown event types, and then sort ascending on the value of the event type.
-                        attributes.retain(|x| x.attribute_type.is_some());
-                        attributes.sort_by(|a, b| {
-                            let aa = a.attribute_type.as_ref().unwrap().to_u64().unwrap();
-                            let bb = b.attribute_type.as_ref().unwrap().to_u64().unwrap();
+        // Filter out unknown event types, and then sort ascending on the value of the event type.
+        attributes.retain(|x| x.attribute_type.is_some());
+        attributes.sort_by_key(|x| x.attribute_type.and_then(|attribute_type| attribute_type.to_u64()).unwrap_or(0));
 
-                            aa.cmp(&bb)
-                        });
+        for attribute in attributes {
+            let attribute_type = match attribute.attribute_type {
+                Some(attribute_type) => attribute_type,
+                None => continue,
+            };
 
-                        for attribute in attributes {
-                            if !attribute.attribute_type.is_some() {
-                                continue;
+            match attribute_type {
+                ReplayAttributeEventType::PlayerTypeAttribute => {
+                    let player_type = match attribute.get_value_str() {
+                        Some(player_type) => match player_type.to_lowercase().as_ref() {
+                            "comp" => Some(PlayerType::Computer),
+                            "humn" => Some(PlayerType::Human),
+                            "open" => None, // Less than 10 players in a Custom game
+                            s => {
+                                replay.warnings.push(format!("unknown player type '{}'", s));
+                                None
                             }
+                        },
+                        None => None
+                    };
 
-                            let attribute_type = attribute.attribute_type.unwrap();
-                            match attribute_type {
-                                ReplayAttributeEventType::PlayerTypeAttribute => {
-                                    match replay.get_player_by_index(attribute.player_id - 1) {
-                                        Some(player) => {
-                                            match attribute.get_value_str() {
-                                                Some(player_type) => {
-                                                    match player_type.to_lowercase().as_ref() {
-                                                        "comp" => {
-                                                            player.player_type = PlayerType::Computer;
-                                                        },
-                                                        "humn" => {
-                                                            player.player_type = PlayerType::Human;
-                                                        },
-                                                        "open" => {
-                                                            // Less than 10 players in a Custom game
-                                                        },
-                                                        s => panic!("unexpected player type: {}", s)
-                                                    }
-                                                },
-                                                None => {}
-                                            }
-                                        },
-                                        None => {}
-                                    }
-                                },
-                                ReplayAttributeEventType::TeamSizeAttribute => {
-                                    match attribute.get_value_str() {
-                                        Some(team_size) => {
-                                            replay.team_size = TeamSize::from_str(&team_size);
-                                        },
-                                        None => {}
-                                    }
-                                },
-                                ReplayAttributeEventType::DifficultyLevelAttribute => {
-                                    match replay.get_player_by_index(attribute.player_id - 1) {
-                                        Some(player) => {
-                                            match attribute.get_value_str() {
-                                                Some(difficulty) => {
-                                                    player.difficulty = Difficulty::from_str(&difficulty);
-                                                },
-                                                None => {}
-                                            }
-                                        },
-                                        None => {}
-                                    }
+                    if let Some(player_type) = player_type {
+                        if let Some(player) = get_attribute_player(replay, &attribute) {
+                            player.player_type = player_type;
+                        }
+                    }
+                },
+                ReplayAttributeEventType::TeamSizeAttribute => {
+                    match attribute.get_value_str() {
+                        Some(team_size) => {
+                            replay.team_size = TeamSize::from_str(&team_size);
+                        },
+                        None => {}
+                    }
+                },
+                ReplayAttributeEventType::DifficultyLevelAttribute => {
+                    match get_attribute_player(replay, &attribute) {
+                        Some(player) => {
+                            match attribute.get_value_str() {
+                                Some(difficulty) => {
+                                    player.difficulty = Difficulty::from_str(&difficulty);
                                 },
-                                ReplayAttributeEventType::GameSpeedAttribute => {
-                                    match attribute.get_value_str() {
-                                        Some(speed) => {
-                                            replay.game_speed = GameSpeed::from_str(&speed);
-                                        },
-                                        None => {}
-                                    }
+                                None => {}
+                            }
+                        },
+                        None => {}
+                    }
+                },
+                ReplayAttributeEventType::GameSpeedAttribute => {
+                    match attribute.get_value_str() {
+                        Some(speed) => {
+                            replay.game_speed = GameSpeed::from_str(&speed);
+                        },
+                        None => {}
+                    }
+                },
+                ReplayAttributeEventType::GameTypeAttribute => {
+                    match attribute.get_value_str() {
+                        Some(game_type) => {
+                            match game_type.to_lowercase().as_ref() {
+                                "priv" => {
+                                    replay.game_mode = GameMode::Custom;
                                 },
-                                ReplayAttributeEventType::GameTypeAttribute => {
-                                    match attribute.get_value_str() {
-                                        Some(game_type) => {
-                                            match game_type.to_lowercase().as_ref() {
-                                                "priv" => {
-                                                    replay.game_mode = GameMode::Custom;
-                                                },
-                                                "amm" => {
-                                                    if replay.replay_build < 33684 {
-                                                        replay.game_mode = GameMode::QuickMatch;
-                                                    }
-                                                },
-                                                s => panic!("unknown game type: {}", s)
-                                            }
-                                        },
-                                        None => {}
+                                "amm" => {
+                                    if replay.replay_build < 33684 {
+                                        replay.game_mode = GameMode::QuickMatch;
                                     }
                                 },
-                                ReplayAttributeEventType::Hero | ReplayAttributeEventType::SkinAndSkinTint => {
-                                    match replay.get_player_by_index(attribute.player_id - 1) {
-                                        Some(player) => {
-                                            match attribute.get_value_str() {
-                                                Some(hero) => {
-                                                    player.is_auto_select = hero == "Rand";
-                                                },
-                                                None => {}
-                                            }
-                                        },
-                                        None => {}
-                                    }
+                                s => replay.warnings.push(format!("unknown game type '{}'", s))
+                            }
+                        },
+                        None => {}
+                    }
+                },
+                ReplayAttributeEventType::Hero | ReplayAttributeEventType::SkinAndSkinTint => {
+                    match get_attribute_player(replay, &attribute) {
+                        Some(player) => {
+                            match attribute.get_value_str() {
+                                Some(hero) => {
+                                    player.is_auto_select = hero == "Rand";
                                 },
-                                ReplayAttributeEventType::CharacterLevel => {
-                                    match replay.get_player_by_index(attribute.player_id - 1) {
-                                        Some(player) => {
-                                            match attribute.get_value_int() {
-                                                Some(level) => {
-                                                    player.character_level = level;
+                                None => {}
+                            }
+                        },
+                        None => {}
+                    }
+                },
+                ReplayAttributeEventType::CharacterLevel => {
+                    match get_attribute_player(replay, &attribute) {
+                        Some(player) => {
+                            match attribute.get_value_int() {
+                                Some(level) => {
+                                    player.character_level = level;
 
-                                                    if player.is_auto_select && player.character_level > 1 {
-                                                        player.is_auto_select = false;
-                                                    }
-                                                },
-                                                None => {}
-                                            }
-                                        },
-                                        None => {}
-                                    }
-                                },
-                                ReplayAttributeEventType::LobbyMode => {
-                                    if replay.replay_build < 43905 && replay.game_mode != GameMode::Custom {
-                                        match attribute.get_value_str() {
-                                            Some(s) => match s.to_lowercase().as_ref() {
-                                                "stan" => {
-                                                    replay.game_mode = GameMode::QuickMatch;
-                                                },
-                                                "drft" => {
-                                                    replay.game_mode = GameMode::HeroLeague;
-                                                },
-                                                s => panic!("unknown game mode: {}", s)
-                                            },
-                                            None => {}
-                                        }
-                                    }
-                                },
-                                ReplayAttributeEventType::ReadyMode => {
-                                    if replay.replay_build < 43905 && replay.game_mode == GameMode::HeroLeague {
-                                        match attribute.get_value_str() {
-                                            Some(s) => {
-                                                if &s == "fcfs" {
-                                                    replay.game_mode = GameMode::TeamLeague;
-                                                }
-                                            },
-                                            None => {}
-                                        }
+                                    if player.is_auto_select && player.character_level > 1 {
+                                        player.is_auto_select = false;
                                     }
                                 },
-                                ReplayAttributeEventType::DraftTeam1BanChooserSlot => {},
-                                ReplayAttributeEventType::DraftTeam2BanChooserSlot => {},
-                                ReplayAttributeEventType::DraftTeam1Ban1 => {
-                                    match attribute.get_value_str() {
-                                        Some(s) => {
-                                            replay.bans.team_one_first_ban = s;
-                                        },
-                                        None => {}
-                                    }
-                                },
-                                ReplayAttributeEventType::DraftTeam1Ban2 => {
-                                    match attribute.get_value_str() {
-                                        Some(s) => {
-                                            replay.bans.team_one_second_ban = s;
-                                        },
-                                        None => {}
-                                    }
-                                },
-                                ReplayAttributeEventType::DraftTeam2Ban1 => {
-                                    match attribute.get_value_str() {
-                                        Some(s) => {
-                                            replay.bans.team_two_first_ban = s;
-                                        },
-                                        None => {}
-                                    }
+                                None => {}
+                            }
+                        },
+                        None => {}
+                    }
+                },
+                ReplayAttributeEventType::LobbyMode => {
+                    if replay.replay_build < 43905 && replay.game_mode != GameMode::Custom {
+                        match attribute.get_value_str() {
+                            Some(s) => match s.to_lowercase().as_ref() {
+                                "stan" => {
+                                    replay.game_mode = GameMode::QuickMatch;
                                 },
-                                ReplayAttributeEventType::DraftTeam2Ban2 => {
-                                    match attribute.get_value_str() {
-                                        Some(s) => {
-                                            replay.bans.team_two_second_ban = s;
-                                        },
-                                        None => {}
-                                    }
+                                "drft" => {
+                                    replay.game_mode = GameMode::HeroLeague;
                                 },
-                                ReplayAttributeEventType::Unknown => {},
-                            }
+                                s => replay.warnings.push(format!("unknown lobby mode '{}'", s))
+                            },
+                            None => {}
                         }
-
-                        Ok(())
-                    },
-                    Err(_) => Err(ReplayError::new(ReplayErrorKind::ArchiveError,  "failed to read attributes file"))
-                }
-            },
-            Err(_) => Err(ReplayError::new(ReplayErrorKind::ArchiveError, "failed to open attributes file"))
+                    }
+                },
+                ReplayAttributeEventType::ReadyMode => {
+                    if replay.replay_build < 43905 && replay.game_mode == GameMode::HeroLeague {
+                        match attribute.get_value_str() {
+                            Some(s) => {
+                                if &s == "fcfs" {
+                                    replay.game_mode = GameMode::TeamLeague;
+                                }
+                            },
+                            None => {}
+                        }
+                    }
+                },
+                ReplayAttributeEventType::DraftTeam1BanChooserSlot => {},
+                ReplayAttributeEventType::DraftTeam2BanChooserSlot => {},
+                ReplayAttributeEventType::DraftTeam1Ban1 => {
+                    match attribute.get_value_str() {
+                        Some(s) => {
+                            replay.draft.steps.push(attribute_ban(0, 0, s));
+                        },
+                        None => {}
+                    }
+                },
+                ReplayAttributeEventType::DraftTeam1Ban2 => {
+                    match attribute.get_value_str() {
+                        Some(s) => {
+                            replay.draft.steps.push(attribute_ban(1, 0, s));
+                        },
+                        None => {}
+                    }
+                },
+                ReplayAttributeEventType::DraftTeam2Ban1 => {
+                    match attribute.get_value_str() {
+                        Some(s) => {
+                            replay.draft.steps.push(attribute_ban(0, 1, s));
+                        },
+                        None => {}
+                    }
+                },
+                ReplayAttributeEventType::DraftTeam2Ban2 => {
+                    match attribute.get_value_str() {
+                        Some(s) => {
+                            replay.draft.steps.push(attribute_ban(1, 1, s));
+                        },
+                        None => {}
+                    }
+                },
+                ReplayAttributeEventType::Unknown => {},
+            }
         }
+
+        Ok(())
+    }
+}
+
+// Player attributes count players from one, with zero left for attributes that apply to the whole game.
+fn get_attribute_player<'a>(replay: &'a mut StormReplay, attribute: &ReplayAttribute) -> Option<&'a mut Player> {
+    match attribute.player_id.checked_sub(1) {
+        Some(index) => replay.get_player_by_index(index).map(|player| {
+            player.attribute_player_id = Some(attribute.player_id);
+            player
+        }),
+        None => None,
+    }
+}
+
+fn attribute_ban(order: u32, team: u32, hero: String) -> DraftStep {
+    DraftStep {
+        order: order,
+        kind: DraftStepKind::Ban,
+        team: Some(team),
+        player: None,
+        hero: hero,
     }
 }
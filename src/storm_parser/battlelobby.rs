@@ -0,0 +1,123 @@
+use storm_parser::archive::ReplayArchive;
+use storm_parser::replay::StormReplay;
+use storm_parser::primitives::*;
+
+// Builds before this one store the BattleTags as bare strings, without the bit-packed length.
+const MIN_BUILD_PREFIXED_TAGS: u32 = 38793;
+
+// Account levels are the sum of every hero's level, which keeps them well under this.  Anything above it means
+// we've read something other than the level.
+const MAX_ACCOUNT_LEVEL: u32 = 10_000;
+
+pub struct ReplayBattleLobby {
+}
+
+impl ReplayBattleLobby {
+    pub fn parse_replay_battle_lobby(replay: &mut StormReplay, archive: &mut ReplayArchive) -> ReplayResult<()> {
+        let file_buf = archive.read_file("replay.server.battlelobby")?;
+
+        // The layout of the battlelobby shifts from build to build: the collection and cosmetic sections
+        // that precede the player info have changed size many times.  Rather than walking all of it, we
+        // anchor on each player's BattleTag, which always starts with their name, and read the fields we
+        // care about relative to it.
+        let prefixed_tags = replay.replay_build >= MIN_BUILD_PREFIXED_TAGS;
+        // Account levels arrived with HotS 2.0.
+        let has_account_level = replay.replay_version_major >= 2;
+
+        // The lobby doesn't list players in the same order as the details, so each search covers the whole
+        // file, skipping any tag another player has already claimed.
+        let mut claimed: Vec<usize> = Vec::new();
+        for player in replay.players.iter_mut() {
+            if let Some(tag) = find_battle_tag(&file_buf, &claimed, &player.name, prefixed_tags) {
+                claimed.push(tag.start);
+                player.battle_tag = Some(tag.text);
+
+                if has_account_level {
+                    player.account_level = read_u32_be(&file_buf, tag.end)
+                        .and_then(|level| if level > 0 && level <= MAX_ACCOUNT_LEVEL { Some(level) } else { None });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct BattleTagMatch {
+    text: String,
+    // Offset of the first byte of the tag, and of the first byte after it.
+    start: usize,
+    end: usize,
+}
+
+fn find_battle_tag(buf: &[u8], claimed: &[usize], name: &str, prefixed: bool) -> Option<BattleTagMatch> {
+    if name.is_empty() {
+        return None
+    }
+
+    let mut needle = name.as_bytes().to_vec();
+    needle.push(b'#');
+
+    let mut offset = 0;
+    while offset + needle.len() <= buf.len() {
+        let start = match find_bytes(&buf[offset..], &needle) {
+            Some(pos) => offset + pos,
+            None => return None,
+        };
+        offset = start + 1;
+
+        if claimed.contains(&start) {
+            continue
+        }
+
+        let digits = buf[start + needle.len()..].iter()
+            .take_while(|c| (**c as char).is_digit(10))
+            .count();
+        if digits == 0 {
+            continue
+        }
+
+        let end = start + needle.len() + digits;
+
+        // Newer builds precede the tag with a flag bit and a 7-bit length, which lets us reject names
+        // that happen to show up elsewhere in the file.
+        if prefixed {
+            if start == 0 || buf[start - 1] as usize != (((end - start) << 1) | 1) {
+                continue
+            }
+        } else if start > 0 && is_name_byte(buf[start - 1]) {
+            // Without the length there's nothing to stop "Bob" from matching the end of "JimBob#123", so make
+            // sure the name doesn't carry on before where we found it.
+            continue
+        }
+
+        return match String::from_utf8(buf[start..end].to_vec()) {
+            Ok(text) => Some(BattleTagMatch { text: text, start: start, end: end }),
+            Err(_) => None,
+        }
+    }
+
+    None
+}
+
+fn is_name_byte(b: u8) -> bool {
+    // Any byte of a multi-byte UTF-8 character has the high bit set.
+    (b as char).is_alphanumeric() || b >= 0x80
+}
+
+fn read_u32_be(buf: &[u8], offset: usize) -> Option<u32> {
+    if offset + 4 > buf.len() {
+        return None
+    }
+
+    let mut value: u32 = 0;
+    for b in buf[offset..offset + 4].iter() {
+        value = (value << 8) | *b as u32;
+    }
+
+    Some(value)
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
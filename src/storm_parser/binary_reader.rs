@@ -99,7 +99,21 @@ impl<'a> BinaryReader<'a> {
         self.read(32).map(|x| x as u32).map(|x| x as i32)
     }
 
+    // Signed fields are stored as unsigned values offset by their minimum, rather than in two's complement.
+    pub fn read_offset_i8(&mut self) -> Result<i32, Error> {
+        self.read(8).map(|x| x as i32 - 128)
+    }
+
+    pub fn read_offset_i32(&mut self) -> Result<i32, Error> {
+        self.read(32).map(|x| (x as i64 - 2147483648) as i32)
+    }
+
     pub fn read_bytes(&mut self, count: u32) -> Result<Vec<u8>, Error> {
+        // Lengths come from the replay itself, so check them before allocating anything.
+        if count as u64 * 8 > (self.len * 8).saturating_sub(self.pos) {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "not enough data left to read"));
+        }
+
         let mut buf: Vec<u8> = vec![0; count as usize];
         self.read_bytes_direct(buf.as_mut_slice())?;
         Ok(buf)
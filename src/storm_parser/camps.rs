@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use storm_parser::replay::StormReplay;
+use storm_parser::tracker::TrackerEventKind;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+pub enum MercCampKind {
+    Siege,
+    Bruiser,
+    Boss,
+    // Map-specific camps, and camps from builds that don't name their type.
+    Other,
+}
+
+/// The mercenary camps captured over the course of the game.
+#[derive(Serialize, Default, Debug)]
+pub struct MercCamps {
+    pub captures: Vec<MercCampCapture>,
+    // Indexed by team.
+    pub team_totals: Vec<u32>,
+}
+
+impl MercCamps {
+    /// The camps of the given kind captured by the given team, in order.
+    pub fn captured_by(&self, team: u32, kind: MercCampKind) -> Vec<&MercCampCapture> {
+        self.captures.iter().filter(|c| c.team == Some(team) && c.kind == kind).collect()
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MercCampCapture {
+    // Identifies the camp within the map.
+    pub camp_id: u32,
+    pub kind: MercCampKind,
+    // The camp type as the game names it, such as "Siege Camp".
+    pub camp_type: Option<String>,
+    pub team: Option<u32>,
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub ticks_elapsed: u32,
+    pub time_sec: u32,
+}
+
+pub struct ReplayMercCamps {
+}
+
+impl ReplayMercCamps {
+    /// Builds the list of mercenary camp captures.
+    ///
+    /// Each camp is announced with its position when the game starts, and every capture after that refers
+    /// back to it by id.
+    pub fn parse_merc_camps(replay: &mut StormReplay) {
+        let mut camp_positions: HashMap<u32, (f64, f64)> = HashMap::new();
+        let mut camps = MercCamps {
+            captures: Vec::new(),
+            team_totals: vec![0, 0],
+        };
+
+        for event in replay.tracker_events.iter() {
+            let stat = match event.kind {
+                TrackerEventKind::StatGame(ref stat) => stat,
+                _ => continue,
+            };
+
+            match stat.name.as_ref() {
+                "JungleCampInit" => {
+                    let camp_id = stat.get_int("CampID");
+                    let position = (stat.get_fixed("PositionX"), stat.get_fixed("PositionY"));
+                    if let (Some(camp_id), (Some(x), Some(y))) = (camp_id, position) {
+                        camp_positions.insert(camp_id as u32, (x, y));
+                    }
+                },
+                "JungleCampCapture" => {
+                    let camp_id = stat.get_int("CampID").unwrap_or(0) as u32;
+                    let camp_type = stat.get_string("CampType").map(|camp_type| camp_type.to_string());
+                    let position = camp_positions.get(&camp_id).cloned();
+
+                    // The capturing team is one-based, and stored as a fixed-point value, of all things.
+                    let team = match stat.get_fixed("TeamID").map(|team| team as u32) {
+                        Some(1) => Some(0),
+                        Some(2) => Some(1),
+                        _ => None,
+                    };
+
+                    if let Some(team) = team {
+                        camps.team_totals[team as usize] += 1;
+                    }
+
+                    camps.captures.push(MercCampCapture {
+                        camp_id: camp_id,
+                        kind: get_camp_kind(camp_type.as_ref().map(|camp_type| camp_type.as_ref())),
+                        camp_type: camp_type,
+                        team: team,
+                        x: position.map(|(x, _)| x),
+                        y: position.map(|(_, y)| y),
+                        ticks_elapsed: event.ticks_elapsed,
+                        time_sec: event.ticks_elapsed / 16,
+                    });
+                },
+                _ => {}
+            }
+        }
+
+        replay.merc_camps = camps;
+    }
+}
+
+fn get_camp_kind(camp_type: Option<&str>) -> MercCampKind {
+    match camp_type {
+        Some("Siege Camp") => MercCampKind::Siege,
+        Some("Bruiser Camp") => MercCampKind::Bruiser,
+        Some("Boss Camp") => MercCampKind::Boss,
+        _ => MercCampKind::Other,
+    }
+}
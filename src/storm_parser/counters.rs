@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+
+use storm_parser::replay::StormReplay;
+use storm_parser::tracker::{TrackerEventKind, StatGameEvent};
+
+pub struct ReplayStatCounters {
+}
+
+impl ReplayStatCounters {
+    /// Collects the simple counter stat events for each player.
+    ///
+    /// A counter is any stat event that carries nothing but the id of the player it fired for, like
+    /// `RegenGlobePickedUp`.  All there is to know about them is how often, and when, they fired, so rather
+    /// than listing them by name, we pick up every event of that shape.
+    pub fn parse_stat_counters(replay: &mut StormReplay) {
+        let mut counters: Vec<HashMap<String, Vec<u32>>> = replay.players.iter().map(|_| HashMap::new()).collect();
+
+        for event in replay.tracker_events.iter() {
+            let stat = match event.kind {
+                TrackerEventKind::StatGame(ref stat) if is_counter(stat) => stat,
+                _ => continue,
+            };
+
+            let position = stat.get_int("PlayerID")
+                .and_then(|player_id| replay.get_player_position_by_tracker_id(player_id as u32));
+            if let Some(position) = position {
+                counters[position].entry(stat.name.clone()).or_insert_with(Vec::new).push(event.ticks_elapsed);
+            }
+        }
+
+        for (player, counters) in replay.players.iter_mut().zip(counters.into_iter()) {
+            player.stat_counters = counters;
+        }
+    }
+}
+
+fn is_counter(stat: &StatGameEvent) -> bool {
+    stat.strings.is_empty() && stat.fixeds.is_empty() &&
+        stat.ints.len() == 1 && stat.get_ints("PlayerID").len() == 1
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use storm_parser::replay::StormReplay;
+use storm_parser::tracker::TrackerEventKind;
+
+/// A hero's death, and who had a hand in it.
+///
+/// Players are referred to by their position in `StormReplay::players`.
+#[derive(Serialize, Clone, Debug)]
+pub struct HeroDeath {
+    pub victim: usize,
+    // Everyone credited with the takedown, which includes whoever landed the killing blow.
+    pub killers: Vec<usize>,
+    pub unit_tag: Option<u32>,
+    pub x: u32,
+    pub y: u32,
+    pub ticks_elapsed: u32,
+    pub time_sec: u32,
+    // When the hero came back, if it did before the game ended.
+    pub respawn_ticks_elapsed: Option<u32>,
+    pub respawn_time_sec: Option<u32>,
+}
+
+// How far behind its PlayerDeath stat event the hero's UnitDied event may be, in ticks.
+const MAX_DEATH_EVENT_LAG: u32 = 16;
+
+// A hero unit dying, as seen by the unit events.
+struct UnitDeath {
+    unit_tag: u32,
+    victim: usize,
+    killer: Option<usize>,
+    x: u32,
+    y: u32,
+    ticks_elapsed: u32,
+    matched: bool,
+}
+
+pub struct ReplayDeaths {
+}
+
+impl ReplayDeaths {
+    /// Builds the timeline of hero deaths.
+    ///
+    /// The unit events tell us which hero unit died, where, and who landed the killing blow, and its revival
+    /// tells us when it came back.  Builds with `PlayerDeath` stat events also give us everyone credited with
+    /// the takedown, so when they're present, we take them as the list of deaths and attach the unit events
+    /// to them.
+    pub fn parse_deaths(replay: &mut StormReplay) {
+        let mut hero_units: HashMap<u32, usize> = HashMap::new();
+        let mut unit_deaths: Vec<UnitDeath> = Vec::new();
+        let mut revivals: Vec<(u32, u32)> = Vec::new();
+        let mut deaths: Vec<HeroDeath> = Vec::new();
+
+        for event in replay.tracker_events.iter() {
+            match event.kind {
+                TrackerEventKind::UnitBorn(ref born) if born.unit_type_name.starts_with("Hero") => {
+                    if let Some(position) = replay.get_player_position_by_tracker_id(born.control_player_id) {
+                        hero_units.insert(born.unit_tag(), position);
+                    }
+                },
+                TrackerEventKind::UnitDied(ref died) => {
+                    if let Some(&victim) = hero_units.get(&died.unit_tag()) {
+                        unit_deaths.push(UnitDeath {
+                            unit_tag: died.unit_tag(),
+                            victim: victim,
+                            killer: died.killer_player_id.and_then(|id| replay.get_player_position_by_tracker_id(id)),
+                            x: died.x,
+                            y: died.y,
+                            ticks_elapsed: event.ticks_elapsed,
+                            matched: false,
+                        });
+                    }
+                },
+                TrackerEventKind::UnitRevived(ref revived) => {
+                    revivals.push((revived.unit_tag(), event.ticks_elapsed));
+                },
+                TrackerEventKind::StatGame(ref stat) if stat.name == "PlayerDeath" => {
+                    let victim = stat.get_int("PlayerID")
+                        .and_then(|id| replay.get_player_position_by_tracker_id(id as u32));
+                    let victim = match victim {
+                        Some(victim) => victim,
+                        None => continue,
+                    };
+
+                    let killers = stat.get_ints("KillingPlayer").iter()
+                        .filter_map(|&player_id| replay.get_player_position_by_tracker_id(player_id as u32))
+                        .collect();
+
+                    let unit_death = unit_deaths.iter_mut().rev()
+                        .take_while(|death| event.ticks_elapsed.saturating_sub(death.ticks_elapsed) <= MAX_DEATH_EVENT_LAG)
+                        .find(|death| death.victim == victim && !death.matched);
+
+                    let death = match unit_death {
+                        Some(unit_death) => {
+                            unit_death.matched = true;
+                            HeroDeath {
+                                victim: victim,
+                                killers: killers,
+                                unit_tag: Some(unit_death.unit_tag),
+                                x: unit_death.x,
+                                y: unit_death.y,
+                                ticks_elapsed: unit_death.ticks_elapsed,
+                                time_sec: unit_death.ticks_elapsed / 16,
+                                respawn_ticks_elapsed: None,
+                                respawn_time_sec: None,
+                            }
+                        },
+                        None => HeroDeath {
+                            victim: victim,
+                            killers: killers,
+                            unit_tag: None,
+                            x: stat.get_fixed("PositionX").unwrap_or(0.0) as u32,
+                            y: stat.get_fixed("PositionY").unwrap_or(0.0) as u32,
+                            ticks_elapsed: event.ticks_elapsed,
+                            time_sec: event.ticks_elapsed / 16,
+                            respawn_ticks_elapsed: None,
+                            respawn_time_sec: None,
+                        },
+                    };
+
+                    deaths.push(death);
+                },
+                _ => {}
+            }
+        }
+
+        // Older builds have no PlayerDeath stat events, so the unit events are all we have to go on.
+        if deaths.is_empty() {
+            deaths = unit_deaths.iter().map(|death| HeroDeath {
+                victim: death.victim,
+                killers: death.killer.into_iter().collect(),
+                unit_tag: Some(death.unit_tag),
+                x: death.x,
+                y: death.y,
+                ticks_elapsed: death.ticks_elapsed,
+                time_sec: death.ticks_elapsed / 16,
+                respawn_ticks_elapsed: None,
+                respawn_time_sec: None,
+            }).collect();
+        }
+
+        for death in deaths.iter_mut() {
+            if let Some(unit_tag) = death.unit_tag {
+                let respawn = revivals.iter()
+                    .find(|&&(tag, ticks_elapsed)| tag == unit_tag && ticks_elapsed > death.ticks_elapsed)
+                    .map(|&(_, ticks_elapsed)| ticks_elapsed);
+
+                death.respawn_ticks_elapsed = respawn;
+                death.respawn_time_sec = respawn.map(|ticks_elapsed| ticks_elapsed / 16);
+            }
+        }
+
+        replay.deaths = deaths;
+    }
+}
@@ -0,0 +1,86 @@
+use storm_parser::replay::StormReplay;
+use storm_parser::events::GameEventKind;
+use storm_parser::structures::StructureKind;
+
+/// A player leaving the game, whether for good or not.
+#[derive(Serialize, Clone, Debug)]
+pub struct Disconnect {
+    // The player's position in `StormReplay::players`.
+    pub player: usize,
+    pub ticks_elapsed: u32,
+    pub time_sec: u32,
+    pub reason: LeaveReason,
+    // Everyone leaves once the game is over, which is nothing to hold against them.  Only known when the tracker
+    // events, which record the core falling, were parsed.
+    pub after_game_end: bool,
+    // When the player rejoined the game, if they did.
+    pub rejoined_ticks_elapsed: Option<u32>,
+}
+
+/// Why a user left the game, from the leave reason of the `GameUserLeaveEvent`.
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+pub enum LeaveReason {
+    // The user quit, which is also how everyone leaves once the game is over.
+    Quit,
+    // The other codes aren't named until a replay with a dropped connection shows which is which, so they're kept
+    // as they are.
+    Other(u32),
+}
+
+impl LeaveReason {
+    pub fn from_u32(value: u32) -> LeaveReason {
+        match value {
+            0 => LeaveReason::Quit,
+            _ => LeaveReason::Other(value),
+        }
+    }
+}
+
+pub struct ReplayDisconnects {
+}
+
+impl ReplayDisconnects {
+    /// Builds the list of players leaving the game, and rejoining it, from the game events.
+    ///
+    /// The game is over once a core falls, and everyone leaving after that is just heading back to the menus.
+    pub fn parse_disconnects(replay: &mut StormReplay) {
+        let game_end_ticks_elapsed = replay.structures.destroyed.iter()
+            .find(|structure| structure.kind == StructureKind::Core)
+            .map(|structure| structure.ticks_elapsed);
+
+        let mut disconnects: Vec<Disconnect> = Vec::new();
+
+        for event in replay.game_events.iter() {
+            match event.kind {
+                GameEventKind::GameUserLeave(ref leave) => {
+                    if let Some(player) = replay.get_game_event_player(event) {
+                        disconnects.push(Disconnect {
+                            player: player,
+                            ticks_elapsed: event.ticks_elapsed,
+                            time_sec: event.ticks_elapsed / 16,
+                            reason: leave.leave_reason,
+                            after_game_end: game_end_ticks_elapsed.map_or(false, |ticks_elapsed| event.ticks_elapsed >= ticks_elapsed),
+                            rejoined_ticks_elapsed: None,
+                        });
+                    }
+                },
+                GameEventKind::GameUserJoin(_) => {
+                    // Everyone joins at the start of the game, so only a join following a leave counts.
+                    if let Some(player) = replay.get_game_event_player(event) {
+                        let disconnect = disconnects.iter_mut()
+                            .rev()
+                            .find(|disconnect| disconnect.player == player);
+                        if let Some(disconnect) = disconnect {
+                            if disconnect.rejoined_ticks_elapsed.is_none() {
+                                disconnect.rejoined_ticks_elapsed = Some(event.ticks_elapsed);
+                            }
+                        }
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        replay.disconnects = disconnects;
+    }
+}
@@ -0,0 +1,86 @@
+use storm_parser::replay::StormReplay;
+use storm_parser::tracker::TrackerEventKind;
+
+/// The bans, picks and swaps of the draft, for modes that have one, in the order they happened.
+#[derive(Serialize, Default, Debug)]
+pub struct Draft {
+    pub steps: Vec<DraftStep>,
+}
+
+impl Draft {
+    /// The heroes banned by the given team, in order.
+    pub fn bans(&self, team: u32) -> Vec<&DraftStep> {
+        self.steps.iter().filter(|step| step.kind == DraftStepKind::Ban && step.team == Some(team)).collect()
+    }
+
+    /// The heroes picked by the given team, in order.
+    pub fn picks(&self, team: u32) -> Vec<&DraftStep> {
+        self.steps.iter().filter(|step| step.kind == DraftStepKind::Pick && step.team == Some(team)).collect()
+    }
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+pub enum DraftStepKind {
+    Ban,
+    Pick,
+    Swap,
+}
+
+/// A single step of the draft.
+#[derive(Serialize, Clone, Debug)]
+pub struct DraftStep {
+    // Zero-based.  Bans read from the attributes don't record which team banned first, so for those this is
+    // only the ban's order within its team.
+    pub order: u32,
+    pub kind: DraftStepKind,
+    pub team: Option<u32>,
+    // The position in `StormReplay::players` of the player picking or receiving the hero.  Bans are made by the
+    // team as a whole.
+    pub player: Option<usize>,
+    // Builds that predate the draft tracker events only record bans, and only by their four-letter attribute
+    // value rather than the full hero name.
+    pub hero: String,
+}
+
+pub struct ReplayDraft {
+}
+
+impl ReplayDraft {
+    /// Rebuilds the draft from the hero ban, pick and swap tracker events.
+    ///
+    /// Builds that predate these events keep the bans read from the attributes.
+    pub fn parse_draft(replay: &mut StormReplay) {
+        let mut steps: Vec<DraftStep> = Vec::new();
+
+        for event in replay.tracker_events.iter() {
+            let (kind, team, player, hero) = match event.kind {
+                // Banning teams are one-based.
+                TrackerEventKind::HeroBanned(ref banned) => {
+                    let team = if banned.controlling_team > 0 { Some(banned.controlling_team - 1) } else { None };
+                    (DraftStepKind::Ban, team, None, &banned.hero)
+                },
+                TrackerEventKind::HeroPicked(ref picked) => {
+                    let player = replay.get_player_position_by_tracker_id(picked.controlling_player);
+                    (DraftStepKind::Pick, player.map(|p| replay.players[p].team), player, &picked.hero)
+                },
+                TrackerEventKind::HeroSwapped(ref swapped) => {
+                    let player = replay.get_player_position_by_tracker_id(swapped.new_controlling_player);
+                    (DraftStepKind::Swap, player.map(|p| replay.players[p].team), player, &swapped.hero)
+                },
+                _ => continue,
+            };
+
+            steps.push(DraftStep {
+                order: steps.len() as u32,
+                kind: kind,
+                team: team,
+                player: player,
+                hero: hero.clone(),
+            });
+        }
+
+        if !steps.is_empty() {
+            replay.draft.steps = steps;
+        }
+    }
+}
@@ -1,661 +1,1024 @@
-use std::io::Error;
-
-use mpq::Archive;
 use num_traits::FromPrimitive;
 
+use storm_parser::archive::ReplayArchive;
 use storm_parser::replay::StormReplay;
 use storm_parser::binary_reader::BinaryReader;
-use storm_parser::tracker::TrackerEventStructure;
+use storm_parser::disconnects::LeaveReason;
 use storm_parser::primitives::*;
 
-#[derive(Serialize, Default, Debug)]
+/// A single event from `replay.game.events`, i.e. an input issued by a player.
+#[derive(Serialize, Debug)]
 pub struct GameEvent {
     pub event_type: ReplayGameEventType,
     pub ticks_elapsed: u32,
+    // The user id of the player who issued the event.  `StormReplay::get_game_event_player` finds their position
+    // in `StormReplay::players`.
     pub player: Option<u32>,
     pub is_global: bool,
-    pub data: Option<TrackerEventStructure>,
+    pub kind: GameEventKind,
 }
 
-pub struct ReplayGameEvents {
+/// The payload of a `GameEvent`.
+///
+/// Events with nothing more to them than their type carry no payload.
+#[derive(Serialize, Debug)]
+pub enum GameEventKind {
+    Unknown,
+    StartGame,
+    DropOurselves,
+    UserFinishedLoadingSync,
+    UserOptions(UserOptionsEvent),
+    BankFile(BankFileEvent),
+    BankSection(BankSectionEvent),
+    BankKey(BankKeyEvent),
+    BankSignature(BankSignatureEvent),
+    CameraSave(CameraSaveEvent),
+    CommandManagerReset(CommandManagerResetEvent),
+    GameCheat(GameCheatEvent),
+    Cmd(CmdEvent),
+    SelectionDelta(SelectionDeltaEvent),
+    ControlGroupUpdate(ControlGroupUpdateEvent),
+    SelectionSyncCheck(SelectionSyncCheckEvent),
+    ResourceTrade(ResourceTradeEvent),
+    TriggerChatMessage(TriggerChatMessageEvent),
+    SetAbsoluteGameSpeed(SetAbsoluteGameSpeedEvent),
+    TriggerPing(TriggerPingEvent),
+    UnitClick(UnitClickEvent),
+    TriggerSkipped,
+    TriggerSoundLengthQuery(TriggerSoundLengthQueryEvent),
+    TriggerSoundOffset(TriggerSoundOffsetEvent),
+    TriggerTransmissionOffset(TriggerTransmissionOffsetEvent),
+    TriggerTransmissionComplete(TriggerTransmissionCompleteEvent),
+    CameraUpdate(CameraUpdateEvent),
+    TriggerPlanetMissionLaunched(TriggerPlanetMissionLaunchedEvent),
+    TriggerDialogControl(TriggerDialogControlEvent),
+    TriggerSoundLengthSync(TriggerSoundLengthSyncEvent),
+    TriggerConversationSkipped(TriggerConversationSkippedEvent),
+    TriggerMouseClicked(TriggerMouseClickedEvent),
+    TriggerMouseMoved(TriggerMouseMovedEvent),
+    TriggerHotkeyPressed(TriggerHotkeyPressedEvent),
+    TriggerTargetModeUpdate(TriggerTargetModeUpdateEvent),
+    TriggerSoundtrackDone(TriggerSoundtrackDoneEvent),
+    TriggerKeyPressed(TriggerKeyPressedEvent),
+    TriggerCutsceneBookmarkFired(TriggerCutsceneBookmarkFiredEvent),
+    TriggerCutsceneEndSceneFired(TriggerCutsceneEndSceneFiredEvent),
+    GameUserLeave(GameUserLeaveEvent),
+    GameUserJoin(GameUserJoinEvent),
+    CommandManagerState(CommandManagerStateEvent),
+    CmdUpdateTargetPoint(CmdUpdateTargetPointEvent),
+    CmdUpdateTargetUnit(CmdUpdateTargetUnitEvent),
+    HeroTalentSelected(HeroTalentSelectedEvent),
+    HeroTalentTreeSelectionPanelToggled(HeroTalentTreeSelectionPanelToggledEvent),
 }
 
-impl ReplayGameEvents {
-    pub fn parse_replay_game_events(replay: &mut StormReplay, archive: &mut Archive) -> ReplayResult<()> {
-        match archive.open_file("replay.game.events") {
-            Ok(file) => {
-                let file_size = file.size();
-                let mut file_buf: Vec<u8> = vec![0; file_size as usize];
-
-                match file.read(archive, file_buf.as_mut()) {
-                    Ok(_) => {
-                        let mut reader = BinaryReader::new(&file_buf);
-                        let mut game_events: Vec<GameEvent> = Vec::new();
-                        let mut ticks_elapsed: u32 = 0;
-
-                        while !reader.eof() {
-                            let mut game_event: GameEvent = Default::default();
-
-                            let ticks_multiplier = reader.read_vu32(2)? << 3;
-                            let ticks_delta = reader.read_vu32(6 + ticks_multiplier)?;
-                            ticks_elapsed += ticks_delta;
-                            game_event.ticks_elapsed = ticks_elapsed;
-
-                            match reader.read_vu32(5)? {
-                                16 => {
-                                    game_event.is_global = true;
-                                },
-                                i => {
-                                    game_event.player = Some(i);
-                                }
-                            };
-
-                            let event_type_raw = reader.read_vu32(7)?;
-                            let event_type = ReplayGameEventType::from_u32(event_type_raw)
-                                .ok_or(ReplayError::new(ReplayErrorKind::StructureError,
-                                    &format!("unknown game event type '{}'", event_type_raw)))?;
-                            game_event.event_type = event_type;
-
-                            game_event.data = match event_type {
-                                ReplayGameEventType::Unknown => None,
-                                ReplayGameEventType::DropOurselvesEvent => None,
-                                ReplayGameEventType::StartGameEvent => None,
-                                ReplayGameEventType::UserFinishedLoadingSyncEvent => None,
-                                ReplayGameEventType::UserOptionsEvent => {
-                                    let mut event = get_tracker_event_array(14);
-
-                                    event.array[0] = get_tracker_event_bool(&mut reader)?; // m_gameFullyDownloaded
-                                    event.array[1] = get_tracker_event_bool(&mut reader)?; // m_developmentCheatsEnabled
-                                    event.array[2] = get_tracker_event_bool(&mut reader)?; // m_testCheatsEnabled
-                                    event.array[3] = get_tracker_event_bool(&mut reader)?; // m_multiplayerCheatsEnabled
-                                    event.array[4] = get_tracker_event_bool(&mut reader)?; // m_syncChecksummingEnabled
-                                    event.array[5] = get_tracker_event_bool(&mut reader)?; // m_isMapToMapTransition
-                                    event.array[6] = get_tracker_event_bool(&mut reader)?; // m_debugPauseEnabled
-                                    event.array[7] = get_tracker_event_bool(&mut reader)?; // m_useGalaxyAsserts
-                                    event.array[8] = get_tracker_event_bool(&mut reader)?; // m_platformMac
-                                    event.array[9] = get_tracker_event_bool(&mut reader)?; // m_cameraFollow
-                                    event.array[10] = get_tracker_event_u32(&mut reader)?; // m_baseBuildNum
-                                    event.array[11] = get_tracker_event_u32(&mut reader)?; // m_buildNum
-                                    event.array[12] = get_tracker_event_u32(&mut reader)?; // m_versionFlags
-                                    event.array[13] = get_tracker_event_blob(&mut reader, 9)?; // m_hotkeyProfile
-
-                                    Some(event)
-                                },
-                                ReplayGameEventType::BankFileEvent => {
-                                    Some(get_tracker_event_blob(&mut reader, 7)?)
-                                },
-                                ReplayGameEventType::BankSectionEvent => {
-                                    Some(get_tracker_event_blob(&mut reader, 6)?)
-                                },
-                                ReplayGameEventType::BankKeyEvent => {
-                                    let mut event = get_tracker_event_array(3);
-                                    event.array[0] = get_tracker_event_blob(&mut reader, 6)?;
-                                    event.array[1] = get_tracker_event_u32(&mut reader)?;
-                                    event.array[2] = get_tracker_event_blob(&mut reader, 7)?;
-
-                                    Some(event)
-                                },
-                                ReplayGameEventType::BankSignatureEvent => {
-                                    let array_len = reader.read_vu32(5)?;
-                                    let mut event = get_tracker_event_array(array_len);
-                                    for _i in 0..array_len {
-                                        event.array.push(get_tracker_event_uint(&mut reader, 8)?);
-                                    }
-                                    event.blob = Some(reader.read_len_prefixed_blob(7)?);
-
-                                    Some(event)
-                                },
-                                ReplayGameEventType::CameraSaveEvent => {
-                                    reader.read_vu32(3)?; // m_which
-                                    reader.read_vu32(16)?; // x
-                                    reader.read_vu32(16)?; // y
-                                    None
-                                },
-                                ReplayGameEventType::CommandManagerResetEvent => {
-                                    reader.read_u32()?; // m_sequence
-                                    None
-                                },
-                                ReplayGameEventType::GameCheatEvent => {
-                                    // m_target
-                                    let mut event = get_tracker_event_array(4);
-
-                                    event.array[0] = match reader.read_vu32(2)? {
-                                        1 => get_tracker_event_point3d(&mut reader)?, // TargetPoint
-                                        2 => get_tracker_event_target_unit(&mut reader)?, // TargetUnit
-                                        _ => get_tracker_event_empty() // None
-                                    };
-
-                                    reader.read_u32()?; // m_time
-                                    reader.read_len_prefixed_string(10)?; // m_verb
-                                    reader.read_len_prefixed_string(10)?; // m_arguments
-
-                                    Some(event)
-                                },
-                                ReplayGameEventType::CmdEvent => {
-                                    let mut event = get_tracker_event_array(5);
-
-                                    // m_cmdFlags
-                                    let cmd_flags_len = if replay.replay_build < 33684     { 22 }
-                                                   else if replay.replay_build < 37117     { 23 }
-                                                   else if replay.replay_build < 38236     { 24 }
-                                                   else if replay.replay_build < 42958     { 25 }
-                                                   else if replay.replay_build < 44256     { 24 }
-                                                   else if replay.replay_build <= 45635    { 26 }
-                                                   else if replay.replay_version_major < 2 { 25 }
-                                                   else                                    { 26 };
-
-                                    let mut cmd_flags = get_tracker_event_array(cmd_flags_len);
-                                    for i in 0..cmd_flags_len {
-                                        cmd_flags.array[i as usize] = get_tracker_event_bool(&mut reader)?;
-                                    }
-                                    event.array[0] = cmd_flags;
-
-                                    // m_abil
-                                    if reader.read_bool()? {
-                                        let mut array = get_tracker_event_array(3);
-
-                                        array.array[0] = get_tracker_event_uint(&mut reader, 16)?; // m_abilLink
-                                        array.array[1] = get_tracker_event_uint(&mut reader, 5)?; // m_abilCmdIndex
-                                        if reader.read_bool()? {
-                                            array.array[2] = get_tracker_event_uint(&mut reader, 8)?; // m_abilCmdData
-                                        }
-
-                                        event.array[1] = array;
-                                    }
-
-                                    // m_data
-                                    event.array[2] = match reader.read_vu32(2)? {
-                                        1 => get_tracker_event_point3d(&mut reader)?, // TargetPoint
-                                        2 => get_tracker_event_target_unit(&mut reader)?, // TargetUnit
-                                        3 => get_tracker_event_u32(&mut reader)?, // Data
-                                        _ => get_tracker_event_empty(), // None or unknown
-                                    };
-
-                                    // m_vector
-                                    if replay.replay_build >= 44256 && reader.read_bool()? {
-                                        get_tracker_event_point3d(&mut reader)?;
-                                    }
-
-                                    if replay.replay_build >= 33684 {
-                                        reader.read_vu32(32)?; // m_sequence
-                                    }
-                                    if reader.read_bool()? {
-                                        event.array[3] = get_tracker_event_u32(&mut reader)?; // m_otherUnit
-                                    }
-                                    if reader.read_bool()? {
-                                        event.array[4] = get_tracker_event_u32(&mut reader)?; // m_unitGroup
-                                    }
-
-                                    Some(event)
-                                },
-                                ReplayGameEventType::SelectionDeltaEvent => {
-                                    let mut event = get_tracker_event_array(2);
-
-                                    event.array[0] = get_tracker_event_uint(&mut reader, 4)?; // m_controlGroupId
-
-                                    let array_bit_len = if replay.replay_version_major < 2 { 9 } else { 6 };
-                                    let index_bit_len = if replay.replay_version_major < 2 { 9 } else { 5 };
-
-                                    // m_delta
-                                    let mut delta = get_tracker_event_array(4);
-                                    delta.array[0] = get_tracker_event_uint(&mut reader, index_bit_len)?;
-
-                                    // m_removeMask
-                                    match reader.read_vu32(2)? {
-                                        0 => {}, // None
-                                        1 => { // Mask
-                                            let mask_bits = reader.read_vu32(array_bit_len)?;
-                                            reader.read_vu32(mask_bits)?;
-                                        },
-                                        2 | 3 => { // OneIndices or ZeroIndices
-                                            let array_len = reader.read_vu32(array_bit_len)?;
-                                            let mut array = get_tracker_event_array(array_len);
-                                            for i in 0..array_len {
-                                                array.array[i as usize] = get_tracker_event_uint(&mut reader, index_bit_len)?;
-                                            }
-
-                                            delta.array[1] = array;
-                                        },
-                                        _ => panic!("unknown m_removeMask value")
-                                    }
-
-                                    // m_addSubgroups
-                                    let subgroup_array_len = reader.read_vu32(array_bit_len)?;
-                                    let mut subgroup_array = get_tracker_event_array(subgroup_array_len);
-                                    for i in 0..subgroup_array_len {
-                                        let mut array = get_tracker_event_array(4);
-                                        array.array[0] = get_tracker_event_uint(&mut reader, 16)?;
-                                        array.array[1] = get_tracker_event_uint(&mut reader, 8)?;
-                                        array.array[2] = get_tracker_event_uint(&mut reader, 8)?;
-                                        array.array[3] = get_tracker_event_uint(&mut reader, array_bit_len)?;
-
-                                        subgroup_array.array[i as usize] = array;
-                                    }
-                                    delta.array[2] = subgroup_array;
-
-                                    // m_addUnitTags
-                                    let unit_array_len = reader.read_vu32(array_bit_len)?;
-                                    let mut unit_array = get_tracker_event_array(unit_array_len);
-                                    for i in 0..unit_array_len {
-                                        unit_array.array[i as usize] = get_tracker_event_u32(&mut reader)?;
-                                    }
-                                    delta.array[3] = unit_array;
-
-                                    event.array[1] = delta;
-
-                                    Some(event)
-                                },
-                                ReplayGameEventType::ControlGroupUpdateEvent => {
-                                    reader.read_vu32(4)?; // m_controlGroupIndex
-
-                                    // m_controlGroupUpdate
-                                    if replay.replay_build < 36359 { // Not sure exactly when this change happened - roughly around here.  This primarily affected 'The Lost Vikings' hero
-                                        reader.read_vu32(2)?;
-                                    } else {
-                                        reader.read_vu32(3)?;
-                                    }
-
-                                    // m_mask
-                                    let bit_len = if replay.replay_version_major < 2 { 9 } else { 6 };
-                                    match reader.read_vu32(2)? {
-                                        1 => { // Mask
-                                            let mask_len = reader.read_vu32(bit_len)?;
-                                            reader.read_vu32(mask_len)?;
-                                            None
-                                        },
-                                        2 | 3 => { // OneIndices or ZeroIndices
-                                            let value_bit_len = if replay.replay_version_major < 2 { 9 } else { 5 };
-                                            let array_len = reader.read_vu32(bit_len)?;
-                                            let mut event = get_tracker_event_array(array_len);
-                                            for i in 0..array_len {
-                                                event.array[i as usize] = get_tracker_event_uint(&mut reader, value_bit_len)?;
-                                            }
-
-                                            Some(event)
-                                        },
-                                        _ => None
-                                    }
-                                },
-                                ReplayGameEventType::SelectionSyncCheckEvent => {
-                                    reader.read_vu32(4)?; // m_controlGroupId
-
-                                    // m_selectionSyncData
-                                    if replay.replay_version_major < 2 {
-                                        reader.read_vu32(9)?; // m_count
-                                        reader.read_vu32(9)?; // m_subgroupCount
-                                        reader.read_vu32(9)?; // m_activeSubgroupIndex
-                                    } else {
-                                        reader.read_vu32(6)?; // m_count
-                                        reader.read_vu32(6)?; // m_subgroupCount
-                                        reader.read_vu32(5)?; // m_activeSubgroupIndex
-                                    }
-
-                                    reader.read_u32()?; // m_unitTagsChecksum
-                                    reader.read_u32()?; // m_subgroupIndicesChecksum
-                                    reader.read_u32()?; // m_subgroupsChecksum
-
-                                    None
-                                },
-                                ReplayGameEventType::ResourceTradeEvent => {
-                                    reader.read_vu32(4)?; // m_recipientId
-                                    reader.read_i32()?; // m_resources, should be offset -2147483648
-                                    reader.read_i32()?; // m_resources, should be offset -2147483648
-                                    reader.read_i32()?; // m_resources, should be offset -2147483648
-
-                                    None
-                                },
-                                ReplayGameEventType::TriggerChatMessageEvent => {
-                                    Some(get_tracker_event_blob(&mut reader, 10)?)
-                                },
-                                ReplayGameEventType::SetAbsoluteGameSpeedEvent => {
-                                    reader.read_vu32(3)?; // m_speed
-                                    None
-                                },
-                                ReplayGameEventType::TriggerPingEvent => {
-                                    let mut event = get_tracker_event_array(5);
-                                    event.array[0] = get_tracker_event_i32(&mut reader)?;
-                                    event.array[1] = get_tracker_event_i32(&mut reader)?;
-                                    event.array[2] = get_tracker_event_u32(&mut reader)?;
-                                    event.array[3] = get_tracker_event_bool(&mut reader)?;
-                                    event.array[4] = get_tracker_event_i32(&mut reader)?;
-
-                                    Some(event)
-                                },
-                                ReplayGameEventType::UnitClickEvent => {
-                                    Some(get_tracker_event_u32(&mut reader)?) // m_unitTag
-                                },
-                                ReplayGameEventType::TriggerSkippedEvent => None,
-                                ReplayGameEventType::TriggerSoundLengthQueryEvent => {
-                                    let mut event = get_tracker_event_array(2);
-                                    event.array[0] = get_tracker_event_u32(&mut reader)?;
-                                    event.array[1] = get_tracker_event_u32(&mut reader)?;
-
-                                    Some(event)
-                                },
-                                ReplayGameEventType::TriggerSoundOffsetEvent => {
-                                    Some(get_tracker_event_u32(&mut reader)?)
-                                },
-                                ReplayGameEventType::TriggerTransmissionOffsetEvent => {
-                                    let mut event = get_tracker_event_array(2);
-                                    event.array[0] = get_tracker_event_i32(&mut reader)?;
-                                    event.array[1] = get_tracker_event_u32(&mut reader)?;
-
-                                    Some(event)
-                                },
-                                ReplayGameEventType::TriggerTransmissionCompleteEvent => {
-                                    Some(get_tracker_event_i32(&mut reader)?)
-                                },
-                                ReplayGameEventType::CameraUpdateEvent => {
-                                    let mut event = get_tracker_event_array(6);
-
-                                    if reader.read_bool()? {
-                                        // m_target, x/y
-                                        let mut array = get_tracker_event_array(2);
-                                        array.array[0] = get_tracker_event_uint(&mut reader, 16)?;
-                                        array.array[1] = get_tracker_event_uint(&mut reader, 16)?;
-
-                                        event.array[0] = array;
-                                    }
-                                    if reader.read_bool()? {
-                                        // m_distance
-                                        event.array[1] = get_tracker_event_uint(&mut reader, 16)?;
-                                    }
-                                    if reader.read_bool()? {
-                                        // m_pitch
-                                        event.array[2] = get_tracker_event_uint(&mut reader, 16)?;
-                                    }
-                                    if reader.read_bool()? {
-                                        // m_yaw
-                                        event.array[3] = get_tracker_event_uint(&mut reader, 16)?;
-                                    }
-                                    if reader.read_bool()? {
-                                        // m_reason
-                                        event.array[4] = get_tracker_event_i8(&mut reader)?;
-                                    }
-
-                                    // m_follow
-                                    event.array[5] = get_tracker_event_bool(&mut reader)?;
-
-                                    Some(event)
-                                },
-                                ReplayGameEventType::TriggerPlanetMissionLaunchedEvent => {
-                                    reader.skip_bytes(4)?; // m_difficultyLevel, i32
-                                    None
-                                },
-                                ReplayGameEventType::TriggerDialogControlEvent => {
-                                    let mut event = get_tracker_event_array(3);
-                                    event.array[0] = get_tracker_event_vint(&mut reader, 32)?;
-                                    event.array[1] = get_tracker_event_vint(&mut reader, 32)?;
-
-                                    event.array[2] = match reader.read_vu32(3)? {
-                                        1 => get_tracker_event_bool(&mut reader)?, // Checked
-                                        2 => get_tracker_event_u32(&mut reader)?, // ValueChanged
-                                        3 => get_tracker_event_i32(&mut reader)?, // SelectionChanged
-                                        4 => get_tracker_event_blob(&mut reader, 11)?, // TextChanged
-                                        5 => get_tracker_event_u32(&mut reader)?, // MouseButton
-                                        _ => get_tracker_event_empty(), // None (0) or unknown
-                                    };
-
-                                    Some(event)
-                                },
-                                ReplayGameEventType::TriggerSoundLengthSyncEvent => {
-                                    let mut event = get_tracker_event_array(2);
-
-                                    let first_array_len = reader.read_vu32(7)?;
-                                    let mut first_array = get_tracker_event_array(first_array_len);
-                                    for i in 0..first_array_len {
-                                        first_array.array[i as usize] = get_tracker_event_u32(&mut reader)?;
-                                    }
-
-                                    let second_array_len = reader.read_vu32(7)?;
-                                    let mut second_array = get_tracker_event_array(first_array_len);
-                                    for i in 0..second_array_len {
-                                        second_array.array[i as usize] = get_tracker_event_u32(&mut reader)?;
-                                    }
-
-                                    event.array[0] = first_array;
-                                    event.array[1] = second_array;
-
-                                    Some(event)
-                                },
-                                ReplayGameEventType::TriggerConversationSkippedEvent => {
-                                    Some(get_tracker_event_bool(&mut reader)?)
-                                },
-                                ReplayGameEventType::TriggerMouseClickedEvent => {
-                                    /*let mut event = get_tracker_event_array(6);
-
-                                    event.array[0] = get_tracker_event_u32(&mut reader)?; // m_button
-                                    event.array[1] = get_tracker_event_bool(&mut reader)?; // m_down
-                                    event.array[2] = get_tracker_event_uint(&mut reader, 11)?; // m_posUI, X
-                                    event.array[3] = get_tracker_event_uint(&mut reader, 11)?; // m_posUI, Y
-                                    event.array[4] = get_tracker_event_point3d(&mut reader)?; // m_posWorld, XYZ
-                                    event.array[5] = get_tracker_event_i8(&mut reader)?; // m_flags
-
-                                    Some(event)*/
-                                    reader.skip_bytes(17)?;
-                                    None
-                                },
-                                ReplayGameEventType::TriggerMouseMovedEvent => {
-                                    /*let mut event = get_tracker_event_array(4);
-
-                                    event.array[0] = get_tracker_event_uint(&mut reader, 11)?; // m_posUI, X
-                                    event.array[1] = get_tracker_event_uint(&mut reader, 11)?; // m_posUI, Y
-                                    event.array[2] = get_tracker_event_point3d(&mut reader)?; // m_posWorld, XYZ
-                                    event.array[3] = get_tracker_event_i8(&mut reader)?; // m_flags
-
-                                    Some(event)*/
-                                    reader.skip_bytes(13)?;
-                                    None
-                                },
-                                ReplayGameEventType::TriggerHotkeyPressedEvent => {
-                                    Some(get_tracker_event_u32(&mut reader)?)
-                                },
-                                ReplayGameEventType::TriggerTargetModeUpdateEvent => {
-                                    reader.read_vu32(16)?; // m_abilLink
-                                    reader.read_vu32(5)?; // m_abilCmdIndex
-                                    reader.read_vu32(8)?; // m_state (-128)
-                                    None
-                                },
-                                ReplayGameEventType::TriggerSoundtrackDoneEvent => {
-                                    Some(get_tracker_event_u32(&mut reader)?)
-                                },
-                                ReplayGameEventType::TriggerKeyPressedEvent => {
-                                    let mut event = get_tracker_event_array(2);
-                                    event.array[0] = get_tracker_event_i8(&mut reader)?;
-                                    event.array[1] = get_tracker_event_i8(&mut reader)?;
-
-                                    Some(event)
-                                },
-                                ReplayGameEventType::TriggerCutsceneBookmarkFiredEvent => {
-                                    let mut event = get_tracker_event_array(2);
-                                    event.array[0] = get_tracker_event_i32(&mut reader)?; // m_cutsceneId
-                                    event.array[1] = get_tracker_event_blob(&mut reader, 7)?; // m_bookmarkName
-
-                                    Some(event)
-                                },
-                                ReplayGameEventType::TriggerCutsceneEndSceneFiredEvent => {
-                                    // m_cutsceneId
-                                    Some(get_tracker_event_i32(&mut reader)?)
-                                },
-                                ReplayGameEventType::GameUserLeaveEvent => {
-                                    // m_leaveReason
-                                    if replay.replay_build >= 55929 {
-                                        reader.read_vu32(5)?;
-                                    } else {
-                                        reader.read_vu32(4)?;
-                                    }
-
-                                    None
-                                },
-                                ReplayGameEventType::GameUserJoinEvent => {
-                                    let mut event = get_tracker_event_array(5);
-                                    event.array[0] = get_tracker_event_uint(&mut reader, 2)?;
-                                    event.array[1] = get_tracker_event_blob(&mut reader, 8)?;
-                                    if reader.read_bool()? {
-                                        event.array[2] = get_tracker_event_blob(&mut reader, 7)?;
-                                    }
-                                    if reader.read_bool()? {
-                                        event.array[3] = get_tracker_event_blob(&mut reader, 8)?;
-                                    }
-                                    if reader.read_bool()? {
-                                        event.array[4] = get_tracker_event_bytes(&mut reader, 40)?;
-                                    }
-
-                                    Some(event)
-                                },
-                                ReplayGameEventType::CommandManagerStateEvent => {
-                                    let mut event = get_tracker_event_uint(&mut reader, 2)?; // m_state
-                                    if replay.replay_build >= 33684 {
-                                        if reader.read_bool()? {
-                                            // m_sequence
-                                            let default = get_tracker_event_empty();
-                                            let mut array = vec![default; 3];
-                                            array[0] = get_tracker_event_vint(&mut reader, 8)?;
-                                            array[1] = get_tracker_event_vint(&mut reader, 8)?;
-                                            array[2] = get_tracker_event_vint(&mut reader, 16)?;
-
-                                            event.array = array;
-                                        }
-                                    }
-
-                                    Some(event)
-                                },
-                                ReplayGameEventType::CmdUpdateTargetPointEvent => {
-                                    if replay.replay_build >= 40336 && reader.read_bool()? {
-                                        reader.skip_bytes(4)?;
-                                    }
-
-                                    Some(get_tracker_event_point3d(&mut reader)?)
-                                },
-                                ReplayGameEventType::CmdUpdateTargetUnitEvent => {
-                                    if replay.replay_build >= 40336 && reader.read_bool()? {
-                                        reader.skip_bytes(4)?;
-                                    }
-
-                                    Some(get_tracker_event_target_unit(&mut reader)?)
-                                },
-                                ReplayGameEventType::HeroTalentSelectedEvent => {
-                                    Some(get_tracker_event_u32(&mut reader)?) // m_index
-                                },
-                                ReplayGameEventType::HeroTalentTreeSelectionPanelToggled => {
-                                    Some(get_tracker_event_bool(&mut reader)?) // m_shown
-                                }
-                            };
-
-                            reader.align();
-                            game_events.push(game_event);
-                        }
+/// A point in the world, in map units.
+#[derive(Serialize, Copy, Clone, Debug)]
+pub struct TargetPoint {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
 
-                        replay.game_events = game_events;
+/// A unit targeted by a command, along with a snapshot of its owner and position at the time.
+#[derive(Serialize, Clone, Debug)]
+pub struct TargetUnit {
+    pub flags: u32,
+    pub timer: u32,
+    pub tag: u32,
+    pub snapshot_unit_link: u32,
+    pub snapshot_control_player_id: Option<u32>,
+    pub snapshot_upkeep_player_id: Option<u32>,
+    pub snapshot_point: TargetPoint,
+}
 
-                        Ok(())
-                    },
-                    Err(_) => Err(ReplayError::new(ReplayErrorKind::ArchiveError,  "failed to read game events file"))
-                }
-            },
-            Err(_) => Err(ReplayError::new(ReplayErrorKind::ArchiveError, "failed to open game events file"))
-        }
-    }
+#[derive(Serialize, Debug)]
+pub struct UserOptionsEvent {
+    pub game_fully_downloaded: bool,
+    pub development_cheats_enabled: bool,
+    pub test_cheats_enabled: bool,
+    pub multiplayer_cheats_enabled: bool,
+    pub sync_checksumming_enabled: bool,
+    pub is_map_to_map_transition: bool,
+    pub debug_pause_enabled: bool,
+    pub use_galaxy_asserts: bool,
+    pub platform_mac: bool,
+    pub camera_follow: bool,
+    pub base_build_num: u32,
+    pub build_num: u32,
+    pub version_flags: u32,
+    pub hotkey_profile: String,
 }
 
-fn get_tracker_event_empty() -> TrackerEventStructure {
-    Default::default()
+#[derive(Serialize, Debug)]
+pub struct BankFileEvent {
+    pub name: String,
 }
 
-fn get_tracker_event_array(slots: u32) -> TrackerEventStructure {
-    let mut event = get_tracker_event_empty();
-    let default = get_tracker_event_empty();
-    event.data_type = 0x00;
-    event.array = vec![default; slots as usize];
-    event
+#[derive(Serialize, Debug)]
+pub struct BankSectionEvent {
+    pub name: String,
 }
 
-fn get_tracker_event_bool(reader: &mut BinaryReader) -> Result<TrackerEventStructure, Error> {
-    get_tracker_event_uint(reader, 1)
+#[derive(Serialize, Debug)]
+pub struct BankKeyEvent {
+    pub name: String,
+    pub key_type: u32,
+    pub data: String,
 }
 
-fn get_tracker_event_uint(reader: &mut BinaryReader, bits: u32) -> Result<TrackerEventStructure, Error> {
-    let mut event = get_tracker_event_empty();
-    let uint = reader.read_vu32(bits)?;
-    event.data_type = 0x07;
-    event.unsigned_int = Some(uint as u64);
-    Ok(event)
+#[derive(Serialize, Debug)]
+pub struct BankSignatureEvent {
+    pub signature: Vec<u32>,
+    pub toon_handle: String,
 }
 
-fn get_tracker_event_vint(reader: &mut BinaryReader, bits: u32) -> Result<TrackerEventStructure, Error> {
-    let mut event = get_tracker_event_empty();
-    let vint = reader.read_vu32(bits)? as i64;
-    event.data_type = 0x09;
-    event.variable_int = Some(vint);
-    Ok(event)
+#[derive(Serialize, Debug)]
+pub struct CameraSaveEvent {
+    pub which: u32,
+    pub x: u32,
+    pub y: u32,
 }
 
-fn get_tracker_event_i8(reader: &mut BinaryReader) -> Result<TrackerEventStructure, Error> {
-    let mut event = get_tracker_event_empty();
-    let vint = (reader.read_vu32(8)? as i8) as i64;
-    event.data_type = 0x09;
-    event.variable_int = Some(vint);
-    Ok(event)
+#[derive(Serialize, Debug)]
+pub struct CommandManagerResetEvent {
+    pub sequence: u32,
 }
 
-fn get_tracker_event_i32(reader: &mut BinaryReader) -> Result<TrackerEventStructure, Error> {
-    let mut event = get_tracker_event_empty();
-    let vint = (reader.read_vu32(32)? as i32) as i64;
-    event.data_type = 0x09;
-    event.variable_int = Some(vint);
-    Ok(event)
+#[derive(Serialize, Debug)]
+pub struct GameCheatEvent {
+    pub target: CheatTarget,
+    pub time: i32,
+    pub verb: String,
+    pub arguments: String,
 }
 
-fn get_tracker_event_u32(reader: &mut BinaryReader) -> Result<TrackerEventStructure, Error> {
-    let mut event = get_tracker_event_empty();
-    let uint = reader.read_u32()?;
-    event.data_type = 0x07;
-    event.unsigned_int = Some(uint as u64);
-    Ok(event)
+#[derive(Serialize, Debug)]
+pub enum CheatTarget {
+    None,
+    Point(TargetPoint),
+    Unit(TargetUnit),
 }
 
-fn get_tracker_event_blob(reader: &mut BinaryReader, blob_len: u32) -> Result<TrackerEventStructure, Error> {
-    let mut event = get_tracker_event_empty();
-    let blob = reader.read_len_prefixed_blob(blob_len)?;
-    event.data_type = 0x02;
-    event.blob = Some(blob);
-    Ok(event)
+/// A command issued to the selected units, such as a move, an attack or an ability.
+#[derive(Serialize, Debug)]
+pub struct CmdEvent {
+    // Bit `n` is set when the `n`th command flag is.
+    pub flags: u32,
+    pub ability: Option<CmdAbility>,
+    pub target: CmdTarget,
+    pub vector: Option<TargetPoint>,
+    pub sequence: Option<u32>,
+    pub other_unit: Option<u32>,
+    pub unit_group: Option<u32>,
 }
 
-fn get_tracker_event_bytes(reader: &mut BinaryReader, bytes: u32) -> Result<TrackerEventStructure, Error> {
-    let mut event = get_tracker_event_empty();
-    let bytes = reader.read_bytes(bytes)?;
-    event.data_type = 0x02;
-    event.blob = Some(bytes);
-    Ok(event)
+#[derive(Serialize, Debug)]
+pub struct CmdAbility {
+    pub ability_link: u32,
+    pub ability_command_index: u32,
+    pub ability_command_data: Option<u32>,
 }
 
-fn get_tracker_event_point3d(reader: &mut BinaryReader) -> Result<TrackerEventStructure, Error> {
-    let x = get_tracker_event_uint(reader, 20)?;
-    let y = get_tracker_event_uint(reader, 20)?;
-    let z = get_tracker_event_i32(reader)?;
+#[derive(Serialize, Debug)]
+pub enum CmdTarget {
+    None,
+    Point(TargetPoint),
+    Unit(TargetUnit),
+    Data(u32),
+}
 
-    let mut array = get_tracker_event_array(3);
-    array.array[0] = x;
-    array.array[1] = y;
-    array.array[2] = z;
+/// Which units of a selection are affected: all of them, those flagged in a bitmask, or those listed
+/// (or not listed) by index.
+#[derive(Serialize, Debug)]
+pub enum SelectionMask {
+    None,
+    Mask(Vec<bool>),
+    OneIndices(Vec<u32>),
+    ZeroIndices(Vec<u32>),
+}
 
-    Ok(array)
+#[derive(Serialize, Debug)]
+pub struct SelectionDeltaEvent {
+    pub control_group_id: u32,
+    pub subgroup_index: u32,
+    pub remove_mask: SelectionMask,
+    pub add_subgroups: Vec<SelectionSubgroup>,
+    pub add_unit_tags: Vec<u32>,
 }
 
-fn get_tracker_event_target_unit(reader: &mut BinaryReader) -> Result<TrackerEventStructure, Error> {
-    let mut event = get_tracker_event_array(7);
-    event.array[0] = get_tracker_event_uint(reader, 16)?; // m_targetUnitFlags
-    event.array[1] = get_tracker_event_uint(reader, 8)?; // m_timer
-    event.array[2] = get_tracker_event_u32(reader)?; // m_tag
-    event.array[3] = get_tracker_event_uint(reader, 16)?; // m_snapshotUnitLink
-    if reader.read_bool()? {
-        event.array[4] = get_tracker_event_uint(reader, 4)?; // m_snapshotControlPlayerId
+#[derive(Serialize, Debug)]
+pub struct SelectionSubgroup {
+    pub unit_link: u32,
+    pub subgroup_priority: u32,
+    pub intra_subgroup_priority: u32,
+    pub count: u32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ControlGroupUpdateEvent {
+    pub control_group_index: u32,
+    // What was done to the control group: set, appended to, recalled, cleared, and so on.
+    pub control_group_update: u32,
+    pub mask: SelectionMask,
+}
+
+/// The state of a control group, which the game checks against its own to make sure selections haven't
+/// drifted out of sync.
+#[derive(Serialize, Debug)]
+pub struct SelectionSyncCheckEvent {
+    pub control_group_id: u32,
+    pub count: u32,
+    pub subgroup_count: u32,
+    pub active_subgroup_index: u32,
+    pub unit_tags_checksum: u32,
+    pub subgroup_indices_checksum: u32,
+    pub subgroups_checksum: u32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ResourceTradeEvent {
+    pub recipient_id: u32,
+    pub resources: Vec<i32>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SetAbsoluteGameSpeedEvent {
+    pub speed: u32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TriggerChatMessageEvent {
+    pub message: String,
+}
+
+/// A ping, in map units.
+#[derive(Serialize, Debug)]
+pub struct TriggerPingEvent {
+    pub x: f64,
+    pub y: f64,
+    pub unit_tag: u32,
+    pub pinged_minimap: bool,
+    pub option: i32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct UnitClickEvent {
+    pub unit_tag: u32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TriggerSoundLengthQueryEvent {
+    pub sound_hash: u32,
+    pub length: u32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TriggerSoundOffsetEvent {
+    pub sound: u32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TriggerTransmissionOffsetEvent {
+    pub transmission_id: i32,
+    pub thread: u32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TriggerTransmissionCompleteEvent {
+    pub transmission_id: i32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TriggerPlanetMissionLaunchedEvent {
+    pub difficulty_level: i32,
+}
+
+/// A mouse click, both where it landed on screen and where that is in the world.
+#[derive(Serialize, Debug)]
+pub struct TriggerMouseClickedEvent {
+    pub button: u32,
+    pub down: bool,
+    pub ui_x: u32,
+    pub ui_y: u32,
+    pub world: TargetPoint,
+    pub flags: i32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TriggerMouseMovedEvent {
+    pub ui_x: u32,
+    pub ui_y: u32,
+    pub world: TargetPoint,
+    pub flags: i32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TriggerTargetModeUpdateEvent {
+    pub ability_link: u32,
+    pub ability_command_index: u32,
+    pub state: i32,
+}
+
+/// A camera movement.  The target is in map units; the other fields are only present when changed.
+#[derive(Serialize, Debug)]
+pub struct CameraUpdateEvent {
+    pub target: Option<CameraTarget>,
+    pub distance: Option<u32>,
+    pub pitch: Option<u32>,
+    pub yaw: Option<u32>,
+    pub reason: Option<i32>,
+    pub follow: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CameraTarget {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TriggerDialogControlEvent {
+    pub control_id: i32,
+    pub event_type: i32,
+    pub event_data: DialogControlEventData,
+}
+
+#[derive(Serialize, Debug)]
+pub enum DialogControlEventData {
+    None,
+    Checked(bool),
+    ValueChanged(u32),
+    SelectionChanged(i32),
+    TextChanged(String),
+    MouseButton(u32),
+}
+
+#[derive(Serialize, Debug)]
+pub struct TriggerSoundLengthSyncEvent {
+    pub sound_hash: Vec<u32>,
+    pub length: Vec<u32>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TriggerConversationSkippedEvent {
+    pub skip_type: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TriggerHotkeyPressedEvent {
+    pub hotkey: u32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TriggerSoundtrackDoneEvent {
+    pub soundtrack: u32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TriggerKeyPressedEvent {
+    pub key: i32,
+    pub flags: i32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TriggerCutsceneBookmarkFiredEvent {
+    pub cutscene_id: i32,
+    pub bookmark_name: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TriggerCutsceneEndSceneFiredEvent {
+    pub cutscene_id: i32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct GameUserLeaveEvent {
+    pub leave_reason: LeaveReason,
+}
+
+#[derive(Serialize, Debug)]
+pub struct GameUserJoinEvent {
+    pub observe: u32,
+    pub name: String,
+    pub toon_handle: Option<String>,
+    pub clan_tag: Option<String>,
+    pub clan_logo: Option<Vec<u8>>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CommandManagerStateEvent {
+    pub state: u32,
+    pub sequence: Option<u32>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CmdUpdateTargetPointEvent {
+    pub sequence: Option<u32>,
+    pub target: TargetPoint,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CmdUpdateTargetUnitEvent {
+    pub sequence: Option<u32>,
+    pub target: TargetUnit,
+}
+
+#[derive(Serialize, Debug)]
+pub struct HeroTalentSelectedEvent {
+    pub index: u32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct HeroTalentTreeSelectionPanelToggledEvent {
+    pub shown: bool,
+}
+
+pub struct ReplayGameEvents {
+}
+
+impl ReplayGameEvents {
+    pub fn parse_replay_game_events(replay: &mut StormReplay, archive: &mut ReplayArchive) -> ReplayResult<()> {
+        let file_buf = archive.read_file("replay.game.events")?;
+        let mut reader = BinaryReader::new(&file_buf);
+        // Events go straight onto the replay, so that if we run into an error partway through, everything
+        // decoded before it is kept.
+        replay.game_events = Vec::new();
+        let mut ticks_elapsed: u32 = 0;
+
+        while !reader.eof() {
+            let ticks_multiplier = reader.read_vu32(2)? << 3;
+            let ticks_delta = reader.read_vu32(6 + ticks_multiplier)?;
+            ticks_elapsed = ticks_elapsed.wrapping_add(ticks_delta);
+
+            let (player, is_global) = match reader.read_vu32(5)? {
+                16 => (None, true),
+                i => (Some(i), false)
+            };
+
+            let event_type_raw = reader.read_vu32(7)?;
+            // Game events carry nothing to tell us how long they are, so there's no getting past one we don't
+            // know.  Rather than throwing away everything before it, we keep what we have and stop there.
+            let event_type = match ReplayGameEventType::from_u32(event_type_raw) {
+                Some(event_type) => event_type,
+                None => {
+                    replay.warnings.push(format!("stopped reading game events at tick {}: unknown game event type '{}'",
+                        ticks_elapsed, event_type_raw));
+                    break;
+                },
+            };
+
+            let kind = match event_type {
+                ReplayGameEventType::Unknown => GameEventKind::Unknown,
+                ReplayGameEventType::DropOurselvesEvent => GameEventKind::DropOurselves,
+                ReplayGameEventType::StartGameEvent => GameEventKind::StartGame,
+                ReplayGameEventType::UserFinishedLoadingSyncEvent => GameEventKind::UserFinishedLoadingSync,
+                ReplayGameEventType::UserOptionsEvent => {
+                    GameEventKind::UserOptions(UserOptionsEvent {
+                        game_fully_downloaded: reader.read_bool()?,
+                        development_cheats_enabled: reader.read_bool()?,
+                        test_cheats_enabled: reader.read_bool()?,
+                        multiplayer_cheats_enabled: reader.read_bool()?,
+                        sync_checksumming_enabled: reader.read_bool()?,
+                        is_map_to_map_transition: reader.read_bool()?,
+                        debug_pause_enabled: reader.read_bool()?,
+                        use_galaxy_asserts: reader.read_bool()?,
+                        platform_mac: reader.read_bool()?,
+                        camera_follow: reader.read_bool()?,
+                        base_build_num: reader.read_u32()?,
+                        build_num: reader.read_u32()?,
+                        version_flags: reader.read_u32()?,
+                        hotkey_profile: read_blob_text(&mut reader, 9)?,
+                    })
+                },
+                ReplayGameEventType::BankFileEvent => {
+                    GameEventKind::BankFile(BankFileEvent { name: read_blob_text(&mut reader, 7)? })
+                },
+                ReplayGameEventType::BankSectionEvent => {
+                    GameEventKind::BankSection(BankSectionEvent { name: read_blob_text(&mut reader, 6)? })
+                },
+                ReplayGameEventType::BankKeyEvent => {
+                    GameEventKind::BankKey(BankKeyEvent {
+                        name: read_blob_text(&mut reader, 6)?,
+                        key_type: reader.read_u32()?,
+                        data: read_blob_text(&mut reader, 7)?,
+                    })
+                },
+                ReplayGameEventType::BankSignatureEvent => {
+                    let array_len = reader.read_vu32(5)?;
+                    let mut signature = Vec::new();
+                    for _i in 0..array_len {
+                        signature.push(reader.read_vu32(8)?);
+                    }
+
+                    GameEventKind::BankSignature(BankSignatureEvent {
+                        signature: signature,
+                        toon_handle: read_blob_text(&mut reader, 7)?,
+                    })
+                },
+                ReplayGameEventType::CameraSaveEvent => {
+                    GameEventKind::CameraSave(CameraSaveEvent {
+                        which: reader.read_vu32(3)?,
+                        x: reader.read_vu32(16)?,
+                        y: reader.read_vu32(16)?,
+                    })
+                },
+                ReplayGameEventType::CommandManagerResetEvent => {
+                    GameEventKind::CommandManagerReset(CommandManagerResetEvent { sequence: reader.read_u32()? })
+                },
+                ReplayGameEventType::GameCheatEvent => {
+                    // m_target
+                    let target = match reader.read_vu32(2)? {
+                        1 => CheatTarget::Point(read_target_point(&mut reader)?),
+                        2 => CheatTarget::Unit(read_target_unit(&mut reader)?),
+                        _ => CheatTarget::None
+                    };
+
+                    GameEventKind::GameCheat(GameCheatEvent {
+                        target: target,
+                        time: reader.read_offset_i32()?, // m_time
+                        verb: read_blob_text(&mut reader, 10)?, // m_verb
+                        arguments: read_blob_text(&mut reader, 10)?, // m_arguments
+                    })
+                },
+                ReplayGameEventType::CmdEvent => {
+                    // m_cmdFlags
+                    let cmd_flags_len = if replay.replay_build < 33684     { 22 }
+                                   else if replay.replay_build < 37117     { 23 }
+                                   else if replay.replay_build < 38236     { 24 }
+                                   else if replay.replay_build < 42958     { 25 }
+                                   else if replay.replay_build < 44256     { 24 }
+                                   else if replay.replay_build <= 45635    { 26 }
+                                   else if replay.replay_version_major < 2 { 25 }
+                                   else                                    { 26 };
+
+                    let mut flags = 0;
+                    for i in 0..cmd_flags_len {
+                        if reader.read_bool()? {
+                            flags |= 1 << i;
+                        }
+                    }
+
+                    // m_abil
+                    let ability = if reader.read_bool()? {
+                        let ability_link = reader.read_vu32(16)?; // m_abilLink
+                        let ability_command_index = reader.read_vu32(5)?; // m_abilCmdIndex
+                        let ability_command_data = if reader.read_bool()? {
+                            Some(reader.read_vu32(8)?) // m_abilCmdData
+                        } else {
+                            None
+                        };
+
+                        Some(CmdAbility {
+                            ability_link: ability_link,
+                            ability_command_index: ability_command_index,
+                            ability_command_data: ability_command_data,
+                        })
+                    } else {
+                        None
+                    };
+
+                    // m_data
+                    let target = match reader.read_vu32(2)? {
+                        1 => CmdTarget::Point(read_target_point(&mut reader)?), // TargetPoint
+                        2 => CmdTarget::Unit(read_target_unit(&mut reader)?), // TargetUnit
+                        3 => CmdTarget::Data(reader.read_u32()?), // Data
+                        _ => CmdTarget::None,
+                    };
+
+                    // m_vector
+                    let vector = if replay.replay_build >= 44256 && reader.read_bool()? {
+                        Some(read_target_point(&mut reader)?)
+                    } else {
+                        None
+                    };
+
+                    let sequence = if replay.replay_build >= 33684 {
+                        Some(reader.read_vu32(32)?) // m_sequence
+                    } else {
+                        None
+                    };
+                    let other_unit = if reader.read_bool()? {
+                        Some(reader.read_u32()?) // m_otherUnit
+                    } else {
+                        None
+                    };
+                    let unit_group = if reader.read_bool()? {
+                        Some(reader.read_u32()?) // m_unitGroup
+                    } else {
+                        None
+                    };
+
+                    GameEventKind::Cmd(CmdEvent {
+                        flags: flags,
+                        ability: ability,
+                        target: target,
+                        vector: vector,
+                        sequence: sequence,
+                        other_unit: other_unit,
+                        unit_group: unit_group,
+                    })
+                },
+                ReplayGameEventType::SelectionDeltaEvent => {
+                    let control_group_id = reader.read_vu32(4)?; // m_controlGroupId
+
+                    let array_bit_len = if replay.replay_version_major < 2 { 9 } else { 6 };
+                    let index_bit_len = if replay.replay_version_major < 2 { 9 } else { 5 };
+
+                    // m_delta
+                    let subgroup_index = reader.read_vu32(index_bit_len)?;
+                    let remove_mask = read_selection_mask(&mut reader, array_bit_len, index_bit_len)?;
+
+                    // m_addSubgroups
+                    let subgroup_array_len = reader.read_vu32(array_bit_len)?;
+                    let mut add_subgroups = Vec::new();
+                    for _ in 0..subgroup_array_len {
+                        add_subgroups.push(SelectionSubgroup {
+                            unit_link: reader.read_vu32(16)?,
+                            subgroup_priority: reader.read_vu32(8)?,
+                            intra_subgroup_priority: reader.read_vu32(8)?,
+                            count: reader.read_vu32(array_bit_len)?,
+                        });
+                    }
+
+                    // m_addUnitTags
+                    let unit_array_len = reader.read_vu32(array_bit_len)?;
+                    let mut add_unit_tags = Vec::new();
+                    for _ in 0..unit_array_len {
+                        add_unit_tags.push(reader.read_u32()?);
+                    }
+
+                    GameEventKind::SelectionDelta(SelectionDeltaEvent {
+                        control_group_id: control_group_id,
+                        subgroup_index: subgroup_index,
+                        remove_mask: remove_mask,
+                        add_subgroups: add_subgroups,
+                        add_unit_tags: add_unit_tags,
+                    })
+                },
+                ReplayGameEventType::ControlGroupUpdateEvent => {
+                    let control_group_index = reader.read_vu32(4)?; // m_controlGroupIndex
+
+                    // m_controlGroupUpdate
+                    let control_group_update = if replay.replay_build < 36359 { // Not sure exactly when this change happened - roughly around here.  This primarily affected 'The Lost Vikings' hero
+                        reader.read_vu32(2)?
+                    } else {
+                        reader.read_vu32(3)?
+                    };
+
+                    // m_mask
+                    let bit_len = if replay.replay_version_major < 2 { 9 } else { 6 };
+                    let value_bit_len = if replay.replay_version_major < 2 { 9 } else { 5 };
+                    let mask = read_selection_mask(&mut reader, bit_len, value_bit_len)?;
+
+                    GameEventKind::ControlGroupUpdate(ControlGroupUpdateEvent {
+                        control_group_index: control_group_index,
+                        control_group_update: control_group_update,
+                        mask: mask,
+                    })
+                },
+                ReplayGameEventType::SelectionSyncCheckEvent => {
+                    let control_group_id = reader.read_vu32(4)?; // m_controlGroupId
+
+                    // m_selectionSyncData
+                    let array_bit_len = if replay.replay_version_major < 2 { 9 } else { 6 };
+                    let index_bit_len = if replay.replay_version_major < 2 { 9 } else { 5 };
+
+                    GameEventKind::SelectionSyncCheck(SelectionSyncCheckEvent {
+                        control_group_id: control_group_id,
+                        count: reader.read_vu32(array_bit_len)?, // m_count
+                        subgroup_count: reader.read_vu32(array_bit_len)?, // m_subgroupCount
+                        active_subgroup_index: reader.read_vu32(index_bit_len)?, // m_activeSubgroupIndex
+                        unit_tags_checksum: reader.read_u32()?, // m_unitTagsChecksum
+                        subgroup_indices_checksum: reader.read_u32()?, // m_subgroupIndicesChecksum
+                        subgroups_checksum: reader.read_u32()?, // m_subgroupsChecksum
+                    })
+                },
+                ReplayGameEventType::ResourceTradeEvent => {
+                    let recipient_id = reader.read_vu32(4)?; // m_recipientId
+
+                    // m_resources
+                    let resources_len = reader.read_vu32(3)?;
+                    let mut resources = Vec::new();
+                    for _ in 0..resources_len {
+                        resources.push(reader.read_offset_i32()?);
+                    }
+
+                    GameEventKind::ResourceTrade(ResourceTradeEvent {
+                        recipient_id: recipient_id,
+                        resources: resources,
+                    })
+                },
+                ReplayGameEventType::TriggerChatMessageEvent => {
+                    GameEventKind::TriggerChatMessage(TriggerChatMessageEvent { message: read_blob_text(&mut reader, 10)? })
+                },
+                ReplayGameEventType::SetAbsoluteGameSpeedEvent => {
+                    GameEventKind::SetAbsoluteGameSpeed(SetAbsoluteGameSpeedEvent { speed: reader.read_vu32(3)? }) // m_speed
+                },
+                ReplayGameEventType::TriggerPingEvent => {
+                    GameEventKind::TriggerPing(TriggerPingEvent {
+                        x: reader.read_offset_i32()? as f64 / 4096.0, // m_point
+                        y: reader.read_offset_i32()? as f64 / 4096.0,
+                        unit_tag: reader.read_u32()?, // m_unit
+                        pinged_minimap: reader.read_bool()?, // m_pingedMinimap
+                        option: reader.read_offset_i32()?, // m_option
+                    })
+                },
+                ReplayGameEventType::UnitClickEvent => {
+                    GameEventKind::UnitClick(UnitClickEvent { unit_tag: reader.read_u32()? })
+                },
+                ReplayGameEventType::TriggerSkippedEvent => GameEventKind::TriggerSkipped,
+                ReplayGameEventType::TriggerSoundLengthQueryEvent => {
+                    GameEventKind::TriggerSoundLengthQuery(TriggerSoundLengthQueryEvent {
+                        sound_hash: reader.read_u32()?,
+                        length: reader.read_u32()?,
+                    })
+                },
+                ReplayGameEventType::TriggerSoundOffsetEvent => {
+                    GameEventKind::TriggerSoundOffset(TriggerSoundOffsetEvent { sound: reader.read_u32()? })
+                },
+                ReplayGameEventType::TriggerTransmissionOffsetEvent => {
+                    GameEventKind::TriggerTransmissionOffset(TriggerTransmissionOffsetEvent {
+                        transmission_id: reader.read_offset_i32()?,
+                        thread: reader.read_u32()?,
+                    })
+                },
+                ReplayGameEventType::TriggerTransmissionCompleteEvent => {
+                    GameEventKind::TriggerTransmissionComplete(TriggerTransmissionCompleteEvent {
+                        transmission_id: reader.read_offset_i32()?,
+                    })
+                },
+                ReplayGameEventType::CameraUpdateEvent => {
+                    // m_target, x/y, in 1/256ths of a map unit
+                    let target = if reader.read_bool()? {
+                        Some(CameraTarget {
+                            x: reader.read_vu32(16)? as f64 / 256.0,
+                            y: reader.read_vu32(16)? as f64 / 256.0,
+                        })
+                    } else {
+                        None
+                    };
+
+                    let distance = read_optional_uint(&mut reader, 16)?; // m_distance
+                    let pitch = read_optional_uint(&mut reader, 16)?; // m_pitch
+                    let yaw = read_optional_uint(&mut reader, 16)?; // m_yaw
+                    let reason = if reader.read_bool()? {
+                        Some(reader.read_offset_i8()?) // m_reason
+                    } else {
+                        None
+                    };
+
+                    GameEventKind::CameraUpdate(CameraUpdateEvent {
+                        target: target,
+                        distance: distance,
+                        pitch: pitch,
+                        yaw: yaw,
+                        reason: reason,
+                        follow: reader.read_bool()?, // m_follow
+                    })
+                },
+                ReplayGameEventType::TriggerPlanetMissionLaunchedEvent => {
+                    GameEventKind::TriggerPlanetMissionLaunched(TriggerPlanetMissionLaunchedEvent {
+                        difficulty_level: reader.read_offset_i32()?, // m_difficultyLevel
+                    })
+                },
+                ReplayGameEventType::TriggerDialogControlEvent => {
+                    let control_id = reader.read_offset_i32()?; // m_controlId
+                    let event_type = reader.read_offset_i32()?; // m_eventType
+
+                    let event_data = match reader.read_vu32(3)? {
+                        1 => DialogControlEventData::Checked(reader.read_bool()?),
+                        2 => DialogControlEventData::ValueChanged(reader.read_u32()?),
+                        3 => DialogControlEventData::SelectionChanged(reader.read_offset_i32()?),
+                        4 => DialogControlEventData::TextChanged(read_blob_text(&mut reader, 11)?),
+                        5 => DialogControlEventData::MouseButton(reader.read_u32()?),
+                        _ => DialogControlEventData::None, // None (0) or unknown
+                    };
+
+                    GameEventKind::TriggerDialogControl(TriggerDialogControlEvent {
+                        control_id: control_id,
+                        event_type: event_type,
+                        event_data: event_data,
+                    })
+                },
+                ReplayGameEventType::TriggerSoundLengthSyncEvent => {
+                    let sound_hash_len = reader.read_vu32(7)?;
+                    let mut sound_hash = Vec::new();
+                    for _ in 0..sound_hash_len {
+                        sound_hash.push(reader.read_u32()?);
+                    }
+
+                    let length_len = reader.read_vu32(7)?;
+                    let mut length = Vec::new();
+                    for _ in 0..length_len {
+                        length.push(reader.read_u32()?);
+                    }
+
+                    GameEventKind::TriggerSoundLengthSync(TriggerSoundLengthSyncEvent {
+                        sound_hash: sound_hash,
+                        length: length,
+                    })
+                },
+                ReplayGameEventType::TriggerConversationSkippedEvent => {
+                    GameEventKind::TriggerConversationSkipped(TriggerConversationSkippedEvent { skip_type: reader.read_bool()? })
+                },
+                ReplayGameEventType::TriggerMouseClickedEvent => {
+                    GameEventKind::TriggerMouseClicked(TriggerMouseClickedEvent {
+                        button: reader.read_u32()?, // m_button
+                        down: reader.read_bool()?, // m_down
+                        ui_x: reader.read_vu32(11)?, // m_posUI
+                        ui_y: reader.read_vu32(11)?,
+                        world: read_target_point(&mut reader)?, // m_posWorld
+                        flags: reader.read_offset_i8()?, // m_flags
+                    })
+                },
+                ReplayGameEventType::TriggerMouseMovedEvent => {
+                    GameEventKind::TriggerMouseMoved(TriggerMouseMovedEvent {
+                        ui_x: reader.read_vu32(11)?, // m_posUI
+                        ui_y: reader.read_vu32(11)?,
+                        world: read_target_point(&mut reader)?, // m_posWorld
+                        flags: reader.read_offset_i8()?, // m_flags
+                    })
+                },
+                ReplayGameEventType::TriggerHotkeyPressedEvent => {
+                    GameEventKind::TriggerHotkeyPressed(TriggerHotkeyPressedEvent { hotkey: reader.read_u32()? })
+                },
+                ReplayGameEventType::TriggerTargetModeUpdateEvent => {
+                    GameEventKind::TriggerTargetModeUpdate(TriggerTargetModeUpdateEvent {
+                        ability_link: reader.read_vu32(16)?, // m_abilLink
+                        ability_command_index: reader.read_vu32(5)?, // m_abilCmdIndex
+                        state: reader.read_offset_i8()?, // m_state
+                    })
+                },
+                ReplayGameEventType::TriggerSoundtrackDoneEvent => {
+                    GameEventKind::TriggerSoundtrackDone(TriggerSoundtrackDoneEvent { soundtrack: reader.read_u32()? })
+                },
+                ReplayGameEventType::TriggerKeyPressedEvent => {
+                    GameEventKind::TriggerKeyPressed(TriggerKeyPressedEvent {
+                        key: reader.read_offset_i8()?,
+                        flags: reader.read_offset_i8()?,
+                    })
+                },
+                ReplayGameEventType::TriggerCutsceneBookmarkFiredEvent => {
+                    GameEventKind::TriggerCutsceneBookmarkFired(TriggerCutsceneBookmarkFiredEvent {
+                        cutscene_id: reader.read_offset_i32()?, // m_cutsceneId
+                        bookmark_name: read_blob_text(&mut reader, 7)?, // m_bookmarkName
+                    })
+                },
+                ReplayGameEventType::TriggerCutsceneEndSceneFiredEvent => {
+                    GameEventKind::TriggerCutsceneEndSceneFired(TriggerCutsceneEndSceneFiredEvent {
+                        cutscene_id: reader.read_offset_i32()?, // m_cutsceneId
+                    })
+                },
+                ReplayGameEventType::GameUserLeaveEvent => {
+                    // m_leaveReason gained a bit when more reasons were added.
+                    let leave_reason = if replay.replay_build >= 55929 {
+                        reader.read_vu32(5)?
+                    } else {
+                        reader.read_vu32(4)?
+                    };
+
+                    GameEventKind::GameUserLeave(GameUserLeaveEvent { leave_reason: LeaveReason::from_u32(leave_reason) })
+                },
+                ReplayGameEventType::GameUserJoinEvent => {
+                    let observe = reader.read_vu32(2)?; // m_observe
+                    let name = read_blob_text(&mut reader, 8)?; // m_name
+                    let toon_handle = if reader.read_bool()? {
+                        Some(read_blob_text(&mut reader, 7)?) // m_toonHandle
+                    } else {
+                        None
+                    };
+                    let clan_tag = if reader.read_bool()? {
+                        Some(read_blob_text(&mut reader, 8)?) // m_clanTag
+                    } else {
+                        None
+                    };
+                    let clan_logo = if reader.read_bool()? {
+                        Some(reader.read_bytes(40)?) // m_clanLogo
+                    } else {
+                        None
+                    };
+
+                    GameEventKind::GameUserJoin(GameUserJoinEvent {
+                        observe: observe,
+                        name: name,
+                        toon_handle: toon_handle,
+                        clan_tag: clan_tag,
+                        clan_logo: clan_logo,
+                    })
+                },
+                ReplayGameEventType::CommandManagerStateEvent => {
+                    let state = reader.read_vu32(2)?; // m_state
+                    let sequence = if replay.replay_build >= 33684 && reader.read_bool()? {
+                        Some(reader.read_u32()?) // m_sequence
+                    } else {
+                        None
+                    };
+
+                    GameEventKind::CommandManagerState(CommandManagerStateEvent { state: state, sequence: sequence })
+                },
+                ReplayGameEventType::CmdUpdateTargetPointEvent => {
+                    let sequence = if replay.replay_build >= 40336 && reader.read_bool()? {
+                        Some(reader.read_u32()?) // m_sequence
+                    } else {
+                        None
+                    };
+
+                    GameEventKind::CmdUpdateTargetPoint(CmdUpdateTargetPointEvent {
+                        sequence: sequence,
+                        target: read_target_point(&mut reader)?, // m_target
+                    })
+                },
+                ReplayGameEventType::CmdUpdateTargetUnitEvent => {
+                    let sequence = if replay.replay_build >= 40336 && reader.read_bool()? {
+                        Some(reader.read_u32()?) // m_sequence
+                    } else {
+                        None
+                    };
+
+                    GameEventKind::CmdUpdateTargetUnit(CmdUpdateTargetUnitEvent {
+                        sequence: sequence,
+                        target: read_target_unit(&mut reader)?, // m_target
+                    })
+                },
+                ReplayGameEventType::HeroTalentSelectedEvent => {
+                    GameEventKind::HeroTalentSelected(HeroTalentSelectedEvent { index: reader.read_u32()? }) // m_index
+                },
+                ReplayGameEventType::HeroTalentTreeSelectionPanelToggled => {
+                    GameEventKind::HeroTalentTreeSelectionPanelToggled(HeroTalentTreeSelectionPanelToggledEvent {
+                        shown: reader.read_bool()?, // m_shown
+                    })
+                }
+            };
+
+            reader.align();
+            replay.game_events.push(GameEvent {
+                event_type: event_type,
+                ticks_elapsed: ticks_elapsed,
+                player: player,
+                is_global: is_global,
+                kind: kind,
+            });
+        }
+
+        Ok(())
     }
+}
+
+fn read_blob_text(reader: &mut BinaryReader, size_bits: u32) -> ReplayResult<String> {
+    let blob = reader.read_len_prefixed_blob(size_bits)?;
+    Ok(String::from_utf8_lossy(&blob).into_owned())
+}
+
+fn read_optional_uint(reader: &mut BinaryReader, bits: u32) -> ReplayResult<Option<u32>> {
     if reader.read_bool()? {
-        event.array[5] = get_tracker_event_uint(reader, 4)?; // m_snapshotUpkeepPlayerId
+        Ok(Some(reader.read_vu32(bits)?))
+    } else {
+        Ok(None)
     }
-    event.array[6] = get_tracker_event_point3d(reader)?; // m_snapshotPoint (X, Y, Z)
+}
+
+fn read_target_point(reader: &mut BinaryReader) -> ReplayResult<TargetPoint> {
+    // All three coordinates are fixed-point values, in 1/4096ths of a map unit.
+    let x = reader.read_vu32(20)? as f64 / 4096.0;
+    let y = reader.read_vu32(20)? as f64 / 4096.0;
+    let z = reader.read_offset_i32()? as f64 / 4096.0;
+
+    Ok(TargetPoint { x: x, y: y, z: z })
+}
+
+fn read_target_unit(reader: &mut BinaryReader) -> ReplayResult<TargetUnit> {
+    Ok(TargetUnit {
+        flags: reader.read_vu32(16)?, // m_targetUnitFlags
+        timer: reader.read_vu32(8)?, // m_timer
+        tag: reader.read_u32()?, // m_tag
+        snapshot_unit_link: reader.read_vu32(16)?, // m_snapshotUnitLink
+        snapshot_control_player_id: read_optional_uint(reader, 4)?, // m_snapshotControlPlayerId
+        snapshot_upkeep_player_id: read_optional_uint(reader, 4)?, // m_snapshotUpkeepPlayerId
+        snapshot_point: read_target_point(reader)?, // m_snapshotPoint (X, Y, Z)
+    })
+}
+
+fn read_selection_mask(reader: &mut BinaryReader, array_bit_len: u32, index_bit_len: u32) -> ReplayResult<SelectionMask> {
+    let mask = match reader.read_vu32(2)? {
+        0 => SelectionMask::None,
+        1 => {
+            let mask_bits = reader.read_vu32(array_bit_len)?;
+            SelectionMask::Mask(reader.read_bit_array(mask_bits)?)
+        },
+        mask_type => {
+            let array_len = reader.read_vu32(array_bit_len)?;
+            let mut indices = Vec::new();
+            for _ in 0..array_len {
+                indices.push(reader.read_vu32(index_bit_len)?);
+            }
+
+            if mask_type == 2 {
+                SelectionMask::OneIndices(indices)
+            } else {
+                SelectionMask::ZeroIndices(indices)
+            }
+        }
+    };
 
-    Ok(event)
+    Ok(mask)
 }
@@ -0,0 +1,79 @@
+use storm_parser::replay::StormReplay;
+use storm_parser::tracker::{TrackerEventKind, PlayerSetupEvent};
+
+/// The numbers a player goes by in the different sections of a replay.
+///
+/// The rest of the model refers to players by their position in `StormReplay::players`, and this is
+/// how each of the other numberings gets there.
+#[derive(Serialize, Clone, Debug)]
+pub struct PlayerIdentity {
+    pub position: usize,
+    // The working set slot id, as used by the details and init data.  Same as `Player::index`.
+    pub slot_id: u32,
+    // One-based, as used by the attributes.  Only known when the attributes were parsed.
+    pub attribute_player_id: Option<u32>,
+    // As used by the game and message events.  Only known when the init data or tracker events were parsed.
+    pub user_id: Option<u32>,
+    // One-based, as used by the tracker events.
+    pub tracker_player_id: u32,
+    pub hero_unit_tag: Option<u32>,
+}
+
+pub struct ReplayIdentities {
+}
+
+impl ReplayIdentities {
+    /// Works out the numbers each player goes by in the different sections of the replay.
+    ///
+    /// The user ids come from the tracker's player setup events, which tie them to lobby slots, and otherwise
+    /// from the lobby slots in the init data.  The attribute ids are whatever the attributes section filed each
+    /// player's attributes under.  The tracker player ids also come from the player setup events, and without
+    /// them we assume the tracker player id is one past the slot, which holds for everything but games with
+    /// observers.
+    ///
+    /// The events themselves keep the ids as the replay has them, and are resolved to a position through the
+    /// lookups on `StormReplay`, such as `get_game_event_player`.
+    pub fn parse_identities(replay: &mut StormReplay) {
+        let mut identities = Vec::new();
+
+        for (position, player) in replay.players.iter().enumerate() {
+            let slot_id = player.index;
+            let setup = find_player_setup(replay, slot_id);
+
+            let user_id = setup.and_then(|setup| setup.user_id).or(player.user_id);
+            let tracker_player_id = setup.map(|setup| setup.player_id).unwrap_or(slot_id + 1);
+
+            // The first hero born under the player's control is theirs.  Clones and the like come later.
+            let hero_unit_tag = replay.tracker_events.iter()
+                .filter_map(|event| match event.kind {
+                    TrackerEventKind::UnitBorn(ref born) => Some(born),
+                    _ => None
+                })
+                .find(|born| born.control_player_id == tracker_player_id && born.unit_type_name.starts_with("Hero"))
+                .map(|born| born.unit_tag());
+
+            identities.push(PlayerIdentity {
+                position: position,
+                slot_id: slot_id,
+                attribute_player_id: player.attribute_player_id,
+                user_id: user_id,
+                tracker_player_id: tracker_player_id,
+                hero_unit_tag: hero_unit_tag,
+            });
+        }
+
+        replay.player_identities = identities;
+    }
+}
+
+fn find_player_setup(replay: &StormReplay, slot_id: u32) -> Option<&PlayerSetupEvent> {
+    for event in replay.tracker_events.iter() {
+        if let TrackerEventKind::PlayerSetup(ref setup) = event.kind {
+            if setup.slot_id == Some(slot_id) {
+                return Some(setup)
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,123 @@
+use storm_parser::replay::StormReplay;
+use storm_parser::tracker::{TrackerEventKind, StatGameEvent};
+
+/// How one of the two teams fared over the course of the game.
+#[derive(Serialize, Default, Debug)]
+pub struct Team {
+    // Zero-based, the same as `Player::team`.
+    pub team: u32,
+    // Each level the team reached, in order.
+    pub levels: Vec<TeamLevel>,
+    // The team's experience to date, sampled every minute once the gates open, plus once at the end of the game.
+    pub xp: Vec<TeamXpSample>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TeamLevel {
+    pub level: u32,
+    pub ticks_elapsed: u32,
+    pub time_sec: u32,
+}
+
+/// A team's experience to date, by source.
+#[derive(Serialize, Clone, Debug)]
+pub struct TeamXpSample {
+    pub ticks_elapsed: u32,
+    pub time_sec: u32,
+    pub level: u32,
+    pub minion_xp: f64,
+    pub creep_xp: f64,
+    pub structure_xp: f64,
+    pub hero_xp: f64,
+    // Experience every team earns passively over time.
+    pub trickle_xp: f64,
+}
+
+impl TeamXpSample {
+    pub fn total_xp(&self) -> f64 {
+        self.minion_xp + self.creep_xp + self.structure_xp + self.hero_xp + self.trickle_xp
+    }
+}
+
+pub struct ReplayLevels {
+}
+
+impl ReplayLevels {
+    /// Builds each team's level and experience timelines.
+    ///
+    /// Players on a team level up together, so the first of them to announce a level marks the team reaching
+    /// it.  The experience breakdowns are already per team, except for the one at the end of the game, which is
+    /// repeated for every player.
+    pub fn parse_levels(replay: &mut StormReplay) {
+        let mut teams: Vec<Team> = (0..2).map(|team| Team { team: team, ..Default::default() }).collect();
+
+        for event in replay.tracker_events.iter() {
+            let stat = match event.kind {
+                TrackerEventKind::StatGame(ref stat) => stat,
+                _ => continue,
+            };
+
+            let ticks_elapsed = event.ticks_elapsed;
+
+            match stat.name.as_ref() {
+                "LevelUp" => {
+                    let team = get_player_team(replay, stat);
+                    let level = stat.get_int("Level");
+                    if let (Some(team), Some(level)) = (team, level) {
+                        let levels = &mut teams[team].levels;
+                        if levels.last().map(|last| last.level < level as u32).unwrap_or(true) {
+                            levels.push(TeamLevel {
+                                level: level as u32,
+                                ticks_elapsed: ticks_elapsed,
+                                time_sec: ticks_elapsed / 16,
+                            });
+                        }
+                    }
+                },
+                "PeriodicXPBreakdown" => {
+                    // These teams are one-based.
+                    let team = stat.get_int("Team").and_then(|team| team.checked_sub(1)).and_then(get_team_index);
+                    if let Some(team) = team {
+                        let level = stat.get_int("TeamLevel").unwrap_or(0) as u32;
+                        teams[team].xp.push(get_xp_sample(stat, ticks_elapsed, level));
+                    }
+                },
+                "EndOfGameXPBreakdown" => {
+                    if let Some(team) = get_player_team(replay, stat) {
+                        let already_sampled = teams[team].xp.last().map(|last| last.ticks_elapsed == ticks_elapsed).unwrap_or(false);
+                        if !already_sampled {
+                            let level = teams[team].levels.last().map(|last| last.level).unwrap_or(0);
+                            teams[team].xp.push(get_xp_sample(stat, ticks_elapsed, level));
+                        }
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        replay.teams = teams;
+    }
+}
+
+fn get_player_team(replay: &StormReplay, stat: &StatGameEvent) -> Option<usize> {
+    stat.get_int("PlayerID")
+        .and_then(|player_id| replay.get_player_position_by_tracker_id(player_id as u32))
+        .and_then(|position| get_team_index(replay.players[position].team as i64))
+}
+
+fn get_team_index(team: i64) -> Option<usize> {
+    if team == 0 || team == 1 { Some(team as usize) } else { None }
+}
+
+fn get_xp_sample(stat: &StatGameEvent, ticks_elapsed: u32, level: u32) -> TeamXpSample {
+    TeamXpSample {
+        ticks_elapsed: ticks_elapsed,
+        time_sec: ticks_elapsed / 16,
+        level: level,
+        minion_xp: stat.get_fixed("MinionXP").unwrap_or(0.0),
+        creep_xp: stat.get_fixed("CreepXP").unwrap_or(0.0),
+        structure_xp: stat.get_fixed("StructureXP").unwrap_or(0.0),
+        hero_xp: stat.get_fixed("HeroXP").unwrap_or(0.0),
+        trickle_xp: stat.get_fixed("TrickleXP").unwrap_or(0.0),
+    }
+}
@@ -0,0 +1,202 @@
+use num_traits::FromPrimitive;
+
+use storm_parser::archive::ReplayArchive;
+use storm_parser::replay::StormReplay;
+use storm_parser::binary_reader::BinaryReader;
+use storm_parser::primitives::*;
+
+/// A single event from `replay.message.events`: chat, pings and loading progress.
+#[derive(Serialize, Debug)]
+pub struct MessageEvent {
+    pub event_type: ReplayMessageEventType,
+    pub ticks_elapsed: u32,
+    // The user id of the player who sent the message.  `StormReplay::get_message_event_player` finds their
+    // position in `StormReplay::players`.
+    pub player: Option<u32>,
+    pub kind: MessageEventKind,
+}
+
+/// The payload of a `MessageEvent`.
+#[derive(Serialize, Debug)]
+pub enum MessageEventKind {
+    Chat(ChatMessage),
+    Ping(PingMessage),
+    LoadingProgress(LoadingProgressMessage),
+    ServerPing,
+    ReconnectNotify(ReconnectNotifyMessage),
+    PlayerAnnounce(PlayerAnnounceMessage),
+}
+
+/// Who a chat message or ping was sent to.
+#[derive(Serialize, Copy, Clone, PartialEq, Debug)]
+pub enum MessageRecipient {
+    All,
+    Allies,
+    Individual,
+    Battlenet,
+    Observers,
+    Unknown,
+}
+
+impl MessageRecipient {
+    fn from_raw(raw: u32) -> MessageRecipient {
+        match raw {
+            0 => MessageRecipient::All,
+            1 => MessageRecipient::Allies,
+            2 => MessageRecipient::Individual,
+            3 => MessageRecipient::Battlenet,
+            4 => MessageRecipient::Observers,
+            _ => MessageRecipient::Unknown,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ChatMessage {
+    pub recipient: MessageRecipient,
+    pub message: String,
+}
+
+/// A minimap ping, with its coordinates in map units.
+#[derive(Serialize, Debug)]
+pub struct PingMessage {
+    pub recipient: MessageRecipient,
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct LoadingProgressMessage {
+    pub progress: i32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReconnectNotifyMessage {
+    pub status: u32,
+}
+
+/// A player announcing the state of an ability or their vitals to their team.
+#[derive(Serialize, Debug)]
+pub struct PlayerAnnounceMessage {
+    pub announcement: Announcement,
+    pub announce_link: Option<u32>,
+    pub other_unit_tag: u32,
+    pub unit_tag: u32,
+}
+
+#[derive(Serialize, Debug)]
+pub enum Announcement {
+    None,
+    Ability { ability_link: u32, ability_index: u32, button_link: u32 },
+    Behavior { behavior_link: u32, button_link: u32 },
+    Vitals { vital_type: i32 },
+}
+
+pub struct ReplayMessageEvents {
+}
+
+impl ReplayMessageEvents {
+    pub fn parse_replay_message_events(replay: &mut StormReplay, archive: &mut ReplayArchive) -> ReplayResult<()> {
+        let file_buf = archive.read_file("replay.message.events")?;
+        let mut reader = BinaryReader::new(&file_buf);
+        // As with the game events, these go straight onto the replay to keep whatever came before an error.
+        replay.message_events = Vec::new();
+        let mut ticks_elapsed: u32 = 0;
+
+        while !reader.eof() {
+            let ticks_multiplier = reader.read_vu32(2)? << 3;
+            let ticks_delta = reader.read_vu32(6 + ticks_multiplier)?;
+            ticks_elapsed = ticks_elapsed.wrapping_add(ticks_delta);
+
+            let player = match reader.read_vu32(5)? {
+                16 => None,
+                i => Some(i)
+            };
+
+            let event_type_raw = reader.read_vu32(4)?;
+            // As with the game events, there's no getting past an event we don't know, so we keep what we have and
+            // stop there.
+            let event_type = match ReplayMessageEventType::from_u32(event_type_raw) {
+                Some(event_type) => event_type,
+                None => {
+                    replay.warnings.push(format!("stopped reading message events at tick {}: unknown message event type '{}'",
+                        ticks_elapsed, event_type_raw));
+                    break;
+                },
+            };
+
+            let kind = match event_type {
+                ReplayMessageEventType::ChatMessage => {
+                    let recipient = MessageRecipient::from_raw(reader.read_vu32(3)?); // m_recipient
+                    let message = reader.read_len_prefixed_string(11)?; // m_string
+
+                    MessageEventKind::Chat(ChatMessage { recipient: recipient, message: message })
+                },
+                ReplayMessageEventType::PingMessage => {
+                    let recipient = MessageRecipient::from_raw(reader.read_vu32(3)?); // m_recipient
+
+                    // m_point, offset by -2147483648 and stored as fixed-point values.
+                    let x = reader.read_offset_i32()? as f64 / 4096.0;
+                    let y = reader.read_offset_i32()? as f64 / 4096.0;
+
+                    MessageEventKind::Ping(PingMessage { recipient: recipient, x: x, y: y })
+                },
+                ReplayMessageEventType::LoadingProgressMessage => {
+                    let progress = reader.read_offset_i32()?; // m_progress
+
+                    MessageEventKind::LoadingProgress(LoadingProgressMessage { progress: progress })
+                },
+                ReplayMessageEventType::ServerPingMessage => MessageEventKind::ServerPing,
+                ReplayMessageEventType::ReconnectNotifyMessage => {
+                    let status = reader.read_vu32(2)?; // m_status
+
+                    MessageEventKind::ReconnectNotify(ReconnectNotifyMessage { status: status })
+                },
+                ReplayMessageEventType::PlayerAnnounceMessage => {
+                    // m_announcement
+                    let announcement = match reader.read_vu32(2)? {
+                        1 => Announcement::Ability {
+                            ability_link: reader.read_vu32(16)?, // m_abilLink
+                            ability_index: reader.read_vu32(5)?, // m_abilCmdIndex
+                            button_link: reader.read_vu32(16)?, // m_buttonLink
+                        },
+                        2 => Announcement::Behavior {
+                            behavior_link: reader.read_vu32(16)?, // m_behaviorLink
+                            button_link: reader.read_vu32(16)?, // m_buttonLink
+                        },
+                        3 => Announcement::Vitals {
+                            vital_type: reader.read_vu32(16)? as i32 - 32768, // m_vital
+                        },
+                        _ => Announcement::None
+                    };
+
+                    let announce_link = if replay.replay_build > 45635 {
+                        Some(reader.read_vu32(16)?) // m_announceLink
+                    } else {
+                        None
+                    };
+
+                    let other_unit_tag = reader.read_u32()?; // m_otherUnitTag
+                    let unit_tag = reader.read_u32()?; // m_unitTag
+
+                    MessageEventKind::PlayerAnnounce(PlayerAnnounceMessage {
+                        announcement: announcement,
+                        announce_link: announce_link,
+                        other_unit_tag: other_unit_tag,
+                        unit_tag: unit_tag,
+                    })
+                }
+            };
+
+            reader.align();
+            replay.message_events.push(MessageEvent {
+                event_type: event_type,
+                ticks_elapsed: ticks_elapsed,
+                player: player,
+                kind: kind,
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+mod archive;
 mod binary_reader;
 mod primitives;
 mod replay;
@@ -5,53 +6,170 @@ mod tracker;
 mod details;
 mod init;
 mod attributes;
+mod battlelobby;
 mod events;
+mod messages;
+mod options;
+mod units;
+mod identities;
+mod draft;
+mod talents;
+mod scores;
+mod levels;
+mod deaths;
+mod structures;
+mod camps;
+mod objectives;
+mod counters;
+mod disconnects;
+
+use std::io::{Cursor, Read, Seek};
 
 use mpq::Archive;
-use self::replay::StormReplay;
-use self::primitives::{ReplayResult, ReplayError, ReplayErrorKind};
 
+pub use self::archive::{ReplayArchive, StreamArchive};
+pub use self::options::ParseOptions;
+pub use self::replay::{StormReplay, PartialReplay};
+pub use self::tracker::{TrackerEvent, TrackerEventStructure, TrackerEventKind, UnitBornEvent, UnitDiedEvent,
+                        UnitOwnerChangeEvent, UnitTypeChangeEvent, UpgradeEvent, UnitDoneEvent, UnitPositionsEvent,
+                        UnitIndexPosition, PlayerSetupEvent, StatGameEvent, ScoreResultEvent,
+                        ScoreResultInstance, ScoreResultValue, UnitRevivedEvent, HeroBannedEvent, HeroPickedEvent,
+                        HeroSwappedEvent, unit_tag};
+pub use self::events::{GameEvent, GameEventKind, TargetPoint, TargetUnit, UserOptionsEvent, BankFileEvent,
+                       BankSectionEvent, BankKeyEvent, BankSignatureEvent, CameraSaveEvent,
+                       CommandManagerResetEvent, GameCheatEvent, CheatTarget, CmdEvent, CmdAbility, CmdTarget,
+                       SelectionMask, SelectionDeltaEvent, SelectionSubgroup, ControlGroupUpdateEvent,
+                       SelectionSyncCheckEvent, ResourceTradeEvent, SetAbsoluteGameSpeedEvent,
+                       TriggerPlanetMissionLaunchedEvent, TriggerMouseClickedEvent, TriggerMouseMovedEvent,
+                       TriggerTargetModeUpdateEvent, TriggerChatMessageEvent, TriggerPingEvent, UnitClickEvent,
+                       TriggerSoundLengthQueryEvent, TriggerSoundOffsetEvent, TriggerTransmissionOffsetEvent,
+                       TriggerTransmissionCompleteEvent, CameraUpdateEvent, CameraTarget,
+                       TriggerDialogControlEvent, DialogControlEventData, TriggerSoundLengthSyncEvent,
+                       TriggerConversationSkippedEvent, TriggerHotkeyPressedEvent, TriggerSoundtrackDoneEvent,
+                       TriggerKeyPressedEvent, TriggerCutsceneBookmarkFiredEvent,
+                       TriggerCutsceneEndSceneFiredEvent, GameUserLeaveEvent, GameUserJoinEvent,
+                       CommandManagerStateEvent,
+                       CmdUpdateTargetPointEvent, CmdUpdateTargetUnitEvent, HeroTalentSelectedEvent,
+                       HeroTalentTreeSelectionPanelToggledEvent};
+pub use self::messages::{MessageEvent, MessageEventKind, MessageRecipient, ChatMessage, PingMessage,
+                         LoadingProgressMessage, ReconnectNotifyMessage, PlayerAnnounceMessage, Announcement};
+pub use self::units::{UnitRegistry, Unit, UnitTypeEntry, UnitOwnerEntry, UnitDeathEntry, UnitPosition,
+                      UnitPositionSample, UnitState};
+pub use self::objectives::{MapObjectives, MapObjectiveExtractor, InfernalShrines, InfernalShrinesObjectives,
+                           ShrineRound, Punisher, get_extractor};
+pub use self::talents::{TalentChoice};
+pub use self::scores::{PlayerScore};
+pub use self::deaths::{HeroDeath};
+pub use self::identities::{PlayerIdentity};
+pub use self::draft::{Draft, DraftStepKind, DraftStep};
+pub use self::levels::{Team, TeamLevel, TeamXpSample};
+pub use self::structures::{StructureKind, Structures, DestroyedStructure};
+pub use self::camps::{MercCampKind, MercCamps, MercCampCapture};
+pub use self::disconnects::{Disconnect, LeaveReason};
+pub use self::primitives::*;
+
+/// Entry points for parsing Heroes of the Storm replays.
+///
+/// The `parse_*` functions return the typed `StormReplay` model, while the `*_json` variants are a
+/// convenience for callers that just want the serialized form.
 pub struct StormParser {
 }
 
 impl StormParser {
-    pub fn parse_replay(replay_file: &String) -> ReplayResult<String> {
-        match Archive::open(replay_file) {
-            Ok(mut archive) => StormParser::parse_archive(&mut archive),
-            Err(_) => Err(ReplayError::new(ReplayErrorKind::FileError, "failed to open archive; does the path exist? is it readable?"))
-        }
-    }
-
-    pub fn validate_replay(replay_file: &String) -> ReplayResult<String> {
-        match Archive::open(replay_file) {
-            Ok(mut archive) => StormParser::validate_archive(&mut archive),
-            Err(_) => Err(ReplayError::new(ReplayErrorKind::FileError, "failed to open archive; does the path exist? is it readable?"))
-        }
-    }
-
-    pub fn parse_archive(archive: &mut Archive) -> ReplayResult<String> {
-        match archive.open_file("(listfile)") {
-            Ok(file) => {
-                let mut buf: Vec<u8> = vec![0; file.size() as usize];
-                match file.read(archive, &mut buf) {
-                    Ok(_) => StormReplay::parse(archive).and_then(|replay| replay.to_json()),
-                    Err(_) => Err(ReplayError::new(ReplayErrorKind::ArchiveError, "failed reading from replay"))
-                }
-            },
-            Err(_) => Err(ReplayError::new(ReplayErrorKind::ArchiveError, "failed to list contents of archive; possible corruption or incompatible replay?"))
-        }
-    }
-
-    pub fn validate_archive(archive: &mut Archive) -> ReplayResult<String> {
-        match archive.open_file("(listfile)") {
-            Ok(file) => {
-                let mut buf: Vec<u8> = vec![0; file.size() as usize];
-                match file.read(archive, &mut buf) {
-                    Ok(_) => StormReplay::validate(archive),
-                    Err(_) => Err(ReplayError::new(ReplayErrorKind::ArchiveError, "failed reading from replay"))
-                }
-            },
-            Err(_) => Err(ReplayError::new(ReplayErrorKind::ArchiveError, "failed to list contents of archive; possible corruption or incompatible replay?"))
-        }
+    /// Parses the replay at the given path.
+    pub fn parse_replay(replay_file: &str) -> ReplayResult<StormReplay> {
+        StormParser::parse_replay_with_options(replay_file, ParseOptions::new())
+    }
+
+    /// Parses the sections of the replay at the given path selected by `options`.
+    pub fn parse_replay_with_options(replay_file: &str, options: ParseOptions) -> ReplayResult<StormReplay> {
+        open_archive(replay_file).and_then(|mut archive| StormParser::parse_archive_with_options(&mut archive, options))
+    }
+
+    /// Parses the sections of the replay at the given path selected by `options`, carrying on past any that fail.
+    ///
+    /// See `StormReplay::parse_lenient`.
+    pub fn parse_replay_lenient(replay_file: &str, options: ParseOptions) -> ReplayResult<PartialReplay> {
+        open_archive(replay_file).and_then(|mut archive| StormParser::parse_archive_lenient(&mut archive, options))
+    }
+
+    /// Parses the replay at the given path and serializes it to JSON.
+    pub fn parse_replay_json(replay_file: &str) -> ReplayResult<String> {
+        StormParser::parse_replay(replay_file).and_then(|replay| replay.to_json())
+    }
+
+    /// Parses just enough of the replay at the given path to compute its unique signature.
+    pub fn validate_replay(replay_file: &str) -> ReplayResult<String> {
+        open_archive(replay_file).and_then(|mut archive| StormParser::validate_archive(&mut archive))
+    }
+
+    /// Parses a replay held in memory, such as an upload body.
+    ///
+    /// Accepts anything that can be viewed as a byte slice, so both `&[u8]` and `Vec<u8>` work.
+    pub fn parse_replay_bytes<B: AsRef<[u8]>>(replay_data: B) -> ReplayResult<StormReplay> {
+        StormParser::parse_replay_reader(Cursor::new(replay_data))
+    }
+
+    /// Parses a replay from any seekable source, without touching the filesystem.
+    pub fn parse_replay_reader<R: Read + Seek>(reader: R) -> ReplayResult<StormReplay> {
+        StormParser::parse_replay_reader_with_options(reader, ParseOptions::new())
+    }
+
+    /// Parses the sections selected by `options` of a replay read from any seekable source.
+    pub fn parse_replay_reader_with_options<R: Read + Seek>(reader: R, options: ParseOptions) -> ReplayResult<StormReplay> {
+        StreamArchive::new(reader).and_then(|mut archive| StormParser::parse_archive_with_options(&mut archive, options))
+    }
+
+    /// Parses the sections selected by `options` of a replay read from any seekable source, carrying on past any
+    /// that fail.
+    pub fn parse_replay_reader_lenient<R: Read + Seek>(reader: R, options: ParseOptions) -> ReplayResult<PartialReplay> {
+        StreamArchive::new(reader).and_then(|mut archive| StormParser::parse_archive_lenient(&mut archive, options))
     }
+
+    /// Computes the unique signature of a replay held in memory.
+    pub fn validate_replay_bytes<B: AsRef<[u8]>>(replay_data: B) -> ReplayResult<String> {
+        StormParser::validate_replay_reader(Cursor::new(replay_data))
+    }
+
+    /// Computes the unique signature of a replay read from any seekable source.
+    pub fn validate_replay_reader<R: Read + Seek>(reader: R) -> ReplayResult<String> {
+        StreamArchive::new(reader).and_then(|mut archive| StormParser::validate_archive(&mut archive))
+    }
+
+    /// Parses a replay from an already opened archive.
+    pub fn parse_archive(archive: &mut ReplayArchive) -> ReplayResult<StormReplay> {
+        StormParser::parse_archive_with_options(archive, ParseOptions::new())
+    }
+
+    /// Parses the sections selected by `options` of a replay from an already opened archive.
+    pub fn parse_archive_with_options(archive: &mut ReplayArchive, options: ParseOptions) -> ReplayResult<StormReplay> {
+        check_archive(archive).and_then(|_| StormReplay::parse_with_options(archive, options))
+    }
+
+    /// Parses the sections selected by `options` of a replay from an already opened archive, carrying on past any
+    /// that fail.
+    pub fn parse_archive_lenient(archive: &mut ReplayArchive, options: ParseOptions) -> ReplayResult<PartialReplay> {
+        check_archive(archive).and_then(|_| StormReplay::parse_lenient(archive, options))
+    }
+
+    /// Parses a replay from an already opened archive and serializes it to JSON.
+    pub fn parse_archive_json(archive: &mut ReplayArchive) -> ReplayResult<String> {
+        StormParser::parse_archive(archive).and_then(|replay| replay.to_json())
+    }
+
+    /// Computes the unique signature of a replay from an already opened archive.
+    pub fn validate_archive(archive: &mut ReplayArchive) -> ReplayResult<String> {
+        check_archive(archive).and_then(|_| StormReplay::validate(archive))
+    }
+}
+
+fn open_archive(replay_file: &str) -> ReplayResult<Archive> {
+    Archive::open(replay_file)
+        .map_err(|_| ReplayError::new(ReplayErrorKind::FileError, "failed to open archive; does the path exist? is it readable?"))
+}
+
+fn check_archive(archive: &mut ReplayArchive) -> ReplayResult<()> {
+    archive.read_file("(listfile)")
+        .map(|_| ())
+        .map_err(|_| ReplayError::new(ReplayErrorKind::ArchiveError, "failed to list contents of archive; possible corruption or incompatible replay?"))
 }
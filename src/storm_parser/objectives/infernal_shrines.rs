@@ -0,0 +1,162 @@
+use storm_parser::replay::StormReplay;
+use storm_parser::tracker::{TrackerEvent, TrackerEventKind};
+use storm_parser::objectives::{MapObjectives, MapObjectiveExtractor};
+
+// Games see a handful of rounds at most, so a round number past this is garbage.
+const MAX_ROUNDS: i64 = 100;
+
+/// The shrine rounds of a game of Infernal Shrines.
+#[derive(Serialize, Default, Debug)]
+pub struct InfernalShrinesObjectives {
+    pub rounds: Vec<ShrineRound>,
+}
+
+/// A single shrine round: from the shrine activating, through the team that won it, to the punisher it
+/// summoned.
+#[derive(Serialize, Default, Debug)]
+pub struct ShrineRound {
+    // One-based.
+    pub round: u32,
+    // When the first cursed minions spawned at the shrine.
+    pub activated_ticks_elapsed: Option<u32>,
+    pub captured_ticks_elapsed: Option<u32>,
+    pub x: Option<u32>,
+    pub y: Option<u32>,
+    pub winning_team: Option<u32>,
+    // How many cursed minions each team killed.
+    pub winning_score: Option<u32>,
+    pub losing_score: Option<u32>,
+    pub punisher: Option<Punisher>,
+}
+
+#[derive(Serialize, Default, Debug)]
+pub struct Punisher {
+    // Such as "ArcaneShrine" or "FrozenShrine".  Only known once the punisher dies.
+    pub punisher_type: Option<String>,
+    pub team: Option<u32>,
+    pub unit_tag: Option<u32>,
+    pub spawned_ticks_elapsed: Option<u32>,
+    pub killed_ticks_elapsed: Option<u32>,
+    pub duration_sec: Option<u32>,
+    pub siege_damage: Option<f64>,
+    pub hero_damage: Option<f64>,
+}
+
+/// Reads the shrine rounds from the shrine and punisher stat events, along with the births of the cursed minions
+/// and punishers, which mark the start of each half of the round.
+pub struct InfernalShrines {
+    rounds: Vec<ShrineRound>,
+    captured: u32,
+}
+
+impl InfernalShrines {
+    pub fn new() -> InfernalShrines {
+        InfernalShrines {
+            rounds: Vec::new(),
+            captured: 0,
+        }
+    }
+
+    /// Whether the event could only have come from a game of Infernal Shrines.  The shrines themselves are
+    /// there from the start of the game, and the stat events follow once the first one is captured.
+    pub fn is_map_event(event: &TrackerEvent) -> bool {
+        match event.kind {
+            TrackerEventKind::UnitBorn(ref born) => born.unit_type_name == "DiabloShrine",
+            TrackerEventKind::StatGame(ref stat) => stat.name == "Infernal Shrine Captured" || stat.name == "Punisher Killed",
+            _ => false,
+        }
+    }
+
+    fn get_round(&mut self, round: u32) -> &mut ShrineRound {
+        while self.rounds.len() < round as usize {
+            let next = self.rounds.len() as u32 + 1;
+            self.rounds.push(ShrineRound { round: next, ..Default::default() });
+        }
+
+        &mut self.rounds[round as usize - 1]
+    }
+
+    fn get_punisher(&mut self, round: u32) -> &mut Punisher {
+        self.get_round(round).punisher.get_or_insert_with(Default::default)
+    }
+}
+
+impl MapObjectiveExtractor for InfernalShrines {
+    fn process_event(&mut self, _replay: &StormReplay, event: &TrackerEvent) {
+        let ticks_elapsed = event.ticks_elapsed;
+
+        match event.kind {
+            TrackerEventKind::UnitBorn(ref born) if born.unit_type_name == "CursedShrineMinion" => {
+                let round = self.captured + 1;
+                let round = self.get_round(round);
+                if round.activated_ticks_elapsed.is_none() {
+                    round.activated_ticks_elapsed = Some(ticks_elapsed);
+                }
+            },
+            TrackerEventKind::UnitBorn(ref born) if born.unit_type_name == "MercPunisherLaner" && self.captured > 0 => {
+                let round = self.captured;
+                let punisher = self.get_punisher(round);
+                punisher.team = get_ai_team(born.control_player_id);
+                punisher.unit_tag = Some(born.unit_tag());
+                punisher.spawned_ticks_elapsed = Some(ticks_elapsed);
+            },
+            TrackerEventKind::StatGame(ref stat) if stat.name == "Infernal Shrine Captured" => {
+                let round = match stat.get_int("Event") {
+                    Some(round) if round > 0 && round <= MAX_ROUNDS => round as u32,
+                    _ => return,
+                };
+                self.captured = round;
+
+                let round = self.get_round(round);
+                round.captured_ticks_elapsed = Some(ticks_elapsed);
+                // These teams are one-based.
+                round.winning_team = stat.get_int("Winning Team").and_then(|team| if team > 0 { Some((team - 1) as u32) } else { None });
+                round.winning_score = stat.get_int("Winning Score").map(|score| score as u32);
+                round.losing_score = stat.get_int("Losing Score").map(|score| score as u32);
+            },
+            TrackerEventKind::StatGame(ref stat) if stat.name == "Punisher Killed" => {
+                let round = match stat.get_int("Event") {
+                    Some(round) if round > 0 && round <= MAX_ROUNDS => round as u32,
+                    _ => return,
+                };
+
+                let punisher = self.get_punisher(round);
+                punisher.punisher_type = stat.get_string("Punisher Type").map(|punisher_type| punisher_type.to_string());
+                punisher.killed_ticks_elapsed = Some(ticks_elapsed);
+                punisher.duration_sec = stat.get_int("Duration").map(|duration| duration as u32);
+                punisher.siege_damage = stat.get_fixed("Siege Damage Done");
+                punisher.hero_damage = stat.get_fixed("Hero Damage Done");
+            },
+            _ => {}
+        }
+    }
+
+    fn finish(mut self: Box<Self>, replay: &StormReplay) -> MapObjectives {
+        // The captured shrine briefly switches to the winning team's AI player, which tells us where it was.
+        for round in self.rounds.iter_mut() {
+            let captured_ticks_elapsed = match round.captured_ticks_elapsed {
+                Some(ticks_elapsed) => ticks_elapsed,
+                None => continue,
+            };
+
+            let shrine = replay.units.units.iter()
+                .filter(|unit| unit.unit_type_name() == "DiabloShrine")
+                .find(|unit| unit.owners.iter().any(|owner| owner.ticks_elapsed == captured_ticks_elapsed));
+            if let Some(position) = shrine.and_then(|shrine| shrine.positions.first()) {
+                round.x = Some(position.x);
+                round.y = Some(position.y);
+            }
+        }
+
+        MapObjectives::InfernalShrines(InfernalShrinesObjectives { rounds: self.rounds })
+    }
+}
+
+// The AI players are numbered 11 and 12.
+fn get_ai_team(player_id: u32) -> Option<u32> {
+    match player_id {
+        11 => Some(0),
+        12 => Some(1),
+        _ => None,
+    }
+}
@@ -0,0 +1,56 @@
+mod infernal_shrines;
+
+use storm_parser::replay::StormReplay;
+use storm_parser::tracker::TrackerEvent;
+
+pub use self::infernal_shrines::{InfernalShrines, InfernalShrinesObjectives, ShrineRound, Punisher};
+
+/// The objectives of the map the game was played on, for maps we know how to read them for.
+#[derive(Serialize, Debug)]
+pub enum MapObjectives {
+    InfernalShrines(InfernalShrinesObjectives),
+}
+
+/// Reads the objectives of a single map out of the tracker events.
+///
+/// Each map gets its own implementation, which `get_extractor` hands out once it recognizes the map.
+pub trait MapObjectiveExtractor {
+    /// Called with every tracker event, in order.
+    fn process_event(&mut self, replay: &StormReplay, event: &TrackerEvent);
+
+    /// Called once all of the tracker events have been seen, to hand over the objectives.
+    fn finish(self: Box<Self>, replay: &StormReplay) -> MapObjectives;
+}
+
+/// Gets the objective extractor for the map the replay was played on, if there is one.
+///
+/// The map name in the details is localized, so we go by the tracker events instead: each map has units and
+/// stat events that show up nowhere else.
+pub fn get_extractor(replay: &StormReplay) -> Option<Box<MapObjectiveExtractor>> {
+    for event in replay.tracker_events.iter() {
+        if InfernalShrines::is_map_event(event) {
+            return Some(Box::new(InfernalShrines::new()))
+        }
+    }
+
+    None
+}
+
+pub struct ReplayObjectives {
+}
+
+impl ReplayObjectives {
+    pub fn parse_objectives(replay: &mut StormReplay) {
+        let objectives = match get_extractor(replay) {
+            Some(mut extractor) => {
+                for event in replay.tracker_events.iter() {
+                    extractor.process_event(replay, event);
+                }
+                Some(extractor.finish(replay))
+            },
+            None => None,
+        };
+
+        replay.objectives = objectives;
+    }
+}
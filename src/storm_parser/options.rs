@@ -0,0 +1,104 @@
+/// Controls which sections of a replay are decoded.
+///
+/// The replay header is always decoded, since every other section depends on the build number it
+/// holds. Likewise, the player list from `replay.details` is decoded whenever any other section is
+/// requested: the init data, the attributes and the battle lobby annotate those players, and everything
+/// derived from the event streams, like scores, talents and deaths, is attributed to them.
+///
+/// ```
+/// use storm_parser::ParseOptions;
+///
+/// // Everything except the game events, which dominate parsing time.
+/// let options = ParseOptions::new().game_events(false);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ParseOptions {
+    pub(crate) details: bool,
+    pub(crate) init: bool,
+    pub(crate) attributes: bool,
+    pub(crate) battle_lobby: bool,
+    pub(crate) game_events: bool,
+    pub(crate) message_events: bool,
+    pub(crate) tracker_events: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions {
+            details: true,
+            init: true,
+            attributes: true,
+            battle_lobby: true,
+            game_events: true,
+            message_events: true,
+            tracker_events: true,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Options that decode every section of the replay.
+    pub fn new() -> ParseOptions {
+        Default::default()
+    }
+
+    /// Options that decode nothing but the replay header.
+    pub fn header_only() -> ParseOptions {
+        ParseOptions {
+            details: false,
+            init: false,
+            attributes: false,
+            battle_lobby: false,
+            game_events: false,
+            message_events: false,
+            tracker_events: false,
+        }
+    }
+
+    /// Whether to decode `replay.details`: the players, map and timestamp.
+    pub fn details(mut self, enabled: bool) -> ParseOptions {
+        self.details = enabled;
+        self
+    }
+
+    /// Whether to decode `replay.initData`: the game mode, map size, skins and mounts.
+    pub fn init(mut self, enabled: bool) -> ParseOptions {
+        self.init = enabled;
+        self
+    }
+
+    /// Whether to decode `replay.attributes.events`: the player types, levels, team size and bans.
+    pub fn attributes(mut self, enabled: bool) -> ParseOptions {
+        self.attributes = enabled;
+        self
+    }
+
+    /// Whether to decode `replay.server.battlelobby`: the BattleTags, account levels and parties.
+    pub fn battle_lobby(mut self, enabled: bool) -> ParseOptions {
+        self.battle_lobby = enabled;
+        self
+    }
+
+    /// Whether to decode `replay.game.events`: every input issued by the players.
+    pub fn game_events(mut self, enabled: bool) -> ParseOptions {
+        self.game_events = enabled;
+        self
+    }
+
+    /// Whether to decode `replay.message.events`: chat messages, pings and loading progress.
+    pub fn message_events(mut self, enabled: bool) -> ParseOptions {
+        self.message_events = enabled;
+        self
+    }
+
+    /// Whether to decode `replay.tracker.events`: the unit, score and stat events.
+    pub fn tracker_events(mut self, enabled: bool) -> ParseOptions {
+        self.tracker_events = enabled;
+        self
+    }
+
+    pub(crate) fn needs_details(&self) -> bool {
+        self.details || self.init || self.attributes || self.battle_lobby ||
+            self.game_events || self.message_events || self.tracker_events
+    }
+}
@@ -1,7 +1,12 @@
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
 use std::io;
 
+use storm_parser::talents::TalentChoice;
+use storm_parser::scores::PlayerScore;
+
+/// Game speed the match was played at.
 #[derive(Primitive, Serialize, Copy, Clone, Debug)]
 pub enum GameSpeed {
     Unknown = 0,
@@ -24,11 +29,12 @@ impl GameSpeed {
             "norm" => GameSpeed::Normal,
             "fast" => GameSpeed::Fast,
             "fasr" => GameSpeed::Faster,
-            _ => panic!("unknown game speed: {}", s)
+            _ => GameSpeed::Unknown
         }
     }
 }
 
+/// Matchmaking mode, or lobby type for custom games.
 #[derive(SignedPrimitive, Serialize, Copy, Clone, PartialEq, Debug)]
 pub enum GameMode {
     Unknown = -9,
@@ -48,6 +54,7 @@ impl Default for GameMode {
     fn default() -> GameMode { GameMode::Unknown }
 }
 
+/// Whether a slot was controlled by a human, the AI or an observer.
 #[derive(Serialize, Copy, Clone, Debug)]
 pub enum PlayerType {
     Human,
@@ -59,6 +66,7 @@ impl Default for PlayerType {
     fn default() -> PlayerType { PlayerType::Computer }
 }
 
+/// Event identifiers used in `replay.tracker.events`.
 #[derive(Primitive, Serialize, Copy, Clone, PartialEq, Debug)]
 pub enum ReplayTrackerEventType {
     Unknown = 0,
@@ -83,6 +91,7 @@ impl Default for ReplayTrackerEventType {
     fn default() -> ReplayTrackerEventType { ReplayTrackerEventType::Unknown }
 }
 
+/// Attribute identifiers used in `replay.attributes.events`.
 #[derive(Primitive, Serialize, Copy, Clone, Debug)]
 pub enum ReplayAttributeEventType
 {
@@ -115,6 +124,7 @@ pub enum ReplayAttributeEventType
     /* 4100 - 4200 are related to Artifacts, no longer in the game */
 }
 
+/// Event identifiers used in `replay.game.events`.
 #[derive(Primitive, Serialize, Copy, Clone, Debug)]
 pub enum ReplayGameEventType
 {
@@ -170,13 +180,27 @@ impl Default for ReplayGameEventType {
     fn default() -> ReplayGameEventType { ReplayGameEventType::Unknown }
 }
 
-#[derive(Serialize, Copy, Clone, Debug)]
+/// Event identifiers used in `replay.message.events`.
+#[derive(Primitive, Serialize, Copy, Clone, PartialEq, Debug)]
+pub enum ReplayMessageEventType
+{
+    ChatMessage = 0,
+    PingMessage = 1,
+    LoadingProgressMessage = 2,
+    ServerPingMessage = 3,
+    ReconnectNotifyMessage = 4,
+    PlayerAnnounceMessage = 5,
+}
+
+/// AI difficulty of a computer-controlled player.
+#[derive(Serialize, Clone, Debug)]
 pub enum Difficulty {
     Beginner,
     Recruit,
     Adept,
     Veteran,
     Elite,
+    Unknown(String),
 }
 
 impl Default for Difficulty {
@@ -191,12 +215,13 @@ impl Difficulty {
             "medi" => Difficulty::Adept,
             "hdvh" => Difficulty::Veteran,
             "vyhd" => Difficulty::Elite,
-            _ => panic!("unknown difficulty type: {}", s)
+            _ => Difficulty::Unknown(s.to_string())
         }
     }
 }
 
-#[derive(Serialize, Copy, Clone, Debug)]
+/// Lobby team size.
+#[derive(Serialize, Clone, Debug)]
 pub enum TeamSize {
     OneVsOne,
     TwoVsTwo,
@@ -204,6 +229,7 @@ pub enum TeamSize {
     FourVsFour,
     FiveVsFive,
     FFA,
+    Unknown(String),
 }
 
 impl Default for TeamSize {
@@ -219,35 +245,52 @@ impl TeamSize {
             "4v4" => TeamSize::FourVsFour,
             "5v5" => TeamSize::FiveVsFive,
             "ffa" => TeamSize::FFA,
-            _ => panic!("unknown team size: {}", s)
+            _ => TeamSize::Unknown(s.to_string())
         }
     }
 }
 
-#[derive(Serialize, Default, Debug)]
-pub struct DraftBans {
-    pub team_one_first_ban: String,
-    pub team_one_second_ban: String,
-    pub team_two_first_ban: String,
-    pub team_two_second_ban: String,
-}
-
-#[derive(Serialize, Copy, Clone, Default)]
+/// A point on the map, in map units.
+#[derive(Serialize, Copy, Clone, Default, Debug)]
 pub struct Point {
     pub x: i32,
     pub y: i32
 }
 
+/// A section of a replay, as decoded by a single pass over one of the files in the archive.
+#[derive(Serialize, Copy, Clone, PartialEq, Debug)]
+pub enum ReplaySection {
+    Header,
+    Details,
+    Init,
+    Attributes,
+    BattleLobby,
+    GameEvents,
+    MessageEvents,
+    TrackerEvents,
+}
+
+/// How decoding a section went: whether all of it was decoded, and if not, the error it ran into, if any.
+#[derive(Serialize, Clone, Debug)]
+pub struct SectionStatus {
+    pub section: ReplaySection,
+    pub complete: bool,
+    pub error: Option<ReplayError>,
+}
+
+/// Result type returned by every parsing function.
 pub type ReplayResult<T> = Result<T, ReplayError>;
 
-#[derive(Debug, Clone)]
+/// Error returned when a replay cannot be parsed.
+#[derive(Serialize, Debug, Clone)]
 pub struct ReplayError {
     pub kind: ReplayErrorKind,
     pub msg: String,
     //pub backtrace: Backtrace
 }
 
-#[derive(Debug, Clone)]
+/// Broad category of a `ReplayError`.
+#[derive(Serialize, Debug, Clone)]
 pub enum ReplayErrorKind {
     FileError,
     ArchiveError,
@@ -291,6 +334,7 @@ impl From<io::Error> for ReplayError {
     }
 }
 
+/// A player slot, merged from the details, init data and attributes of the replay.
 #[derive(Serialize, Default, Debug)]
 pub struct Player {
     pub name: String,
@@ -298,7 +342,13 @@ pub struct Player {
     pub battlenet_region_id: u32,
     pub battlenet_sub_id: u32,
     pub battlenet_id: u32,
+    pub battle_tag: Option<String>,
+    pub account_level: Option<u32>,
     pub index: u32,
+    // The user id of the player's lobby slot in the init data, as used by the game and message events.
+    pub user_id: Option<u32>,
+    // The id the attributes section gave the player's attributes under, if it had any for them.
+    pub attribute_player_id: Option<u32>,
     // 4-bytes long, ARGB
     pub color: Vec<u32>,
     pub team: u32,
@@ -311,4 +361,21 @@ pub struct Player {
     pub mount: Option<String>,
     pub difficulty: Difficulty,
     pub is_auto_select: bool,
+    pub talents: Vec<TalentChoice>,
+    pub score: Option<PlayerScore>,
+    // The ticks at which each of the simple counter stat events, such as `RegenGlobePickedUp`, fired for
+    // this player, keyed by event name.
+    pub stat_counters: HashMap<String, Vec<u32>>,
+}
+
+impl Player {
+    /// The ticks at which the player picked up a regen globe.
+    pub fn regen_globes(&self) -> &[u32] {
+        self.get_stat_counter("RegenGlobePickedUp")
+    }
+
+    /// The ticks at which the given counter stat event fired for the player.
+    pub fn get_stat_counter(&self, name: &str) -> &[u32] {
+        self.stat_counters.get(name).map(|ticks| ticks.as_slice()).unwrap_or(&[])
+    }
 }
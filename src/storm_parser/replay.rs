@@ -2,19 +2,35 @@ use std::fmt::Write;
 
 use md5;
 use chrono::prelude::*;
-use mpq::Archive;
 use serde_json;
 use uuid::Uuid;
 
+use storm_parser::archive::ReplayArchive;
 use storm_parser::binary_reader::BinaryReader;
 use storm_parser::tracker::{TrackerEventStructure, TrackerEvent, ReplayTrackerEvents};
 use storm_parser::details::ReplayDetails;
 use storm_parser::init::ReplayInit;
 use storm_parser::attributes::ReplayAttributes;
+use storm_parser::battlelobby::ReplayBattleLobby;
 use storm_parser::events::{GameEvent, ReplayGameEvents};
+use storm_parser::messages::{MessageEvent, ReplayMessageEvents};
+use storm_parser::options::ParseOptions;
+use storm_parser::units::{UnitRegistry, ReplayUnits};
+use storm_parser::identities::{PlayerIdentity, ReplayIdentities};
+use storm_parser::draft::{Draft, ReplayDraft};
+use storm_parser::talents::ReplayTalents;
+use storm_parser::scores::ReplayScores;
+use storm_parser::levels::{Team, ReplayLevels};
+use storm_parser::deaths::{HeroDeath, ReplayDeaths};
+use storm_parser::structures::{Structures, ReplayStructures};
+use storm_parser::camps::{MercCamps, ReplayMercCamps};
+use storm_parser::objectives::{MapObjectives, ReplayObjectives};
+use storm_parser::counters::ReplayStatCounters;
+use storm_parser::disconnects::{Disconnect, ReplayDisconnects};
 use storm_parser::primitives::*;
 
-#[derive(Derivative, Serialize)]
+/// A parsed replay: metadata, players and the decoded event streams.
+#[derive(Derivative, Serialize, Debug)]
 #[derivative(Default)]
 pub struct StormReplay {
     // High-level attributes of the self.
@@ -33,33 +49,149 @@ pub struct StormReplay {
     pub map_size: Point,
 
     pub players: Vec<Player>,
+    pub player_identities: Vec<PlayerIdentity>,
     pub team_size: TeamSize,
-    pub bans: DraftBans,
+    pub draft: Draft,
 
     pub game_events: Vec<GameEvent>,
+    pub message_events: Vec<MessageEvent>,
     pub tracker_events: Vec<TrackerEvent>,
+
+    pub units: UnitRegistry,
+    pub teams: Vec<Team>,
+    pub deaths: Vec<HeroDeath>,
+    pub structures: Structures,
+    pub merc_camps: MercCamps,
+    pub objectives: Option<MapObjectives>,
+    pub disconnects: Vec<Disconnect>,
+
+    // Anything we had to give up on while parsing, short of failing outright, such as event types from a
+    // newer patch than we know about.
+    pub warnings: Vec<String>,
+}
+
+/// The result of a lenient parse: the replay, with whatever could be decoded, and how each section went.
+#[derive(Serialize, Debug)]
+pub struct PartialReplay {
+    pub replay: StormReplay,
+    // One entry per section that was asked for, in the order they were parsed.
+    pub sections: Vec<SectionStatus>,
+}
+
+impl PartialReplay {
+    /// Whether every section that was asked for was decoded in full.
+    pub fn is_complete(&self) -> bool {
+        self.sections.iter().all(|status| status.complete)
+    }
+
+    /// The status of the given section, if it was asked for.
+    pub fn get_section(&self, section: ReplaySection) -> Option<&SectionStatus> {
+        self.sections.iter().find(|status| status.section == section)
+    }
+
+    /// The errors of the sections that failed.
+    pub fn errors(&self) -> Vec<&ReplayError> {
+        self.sections.iter().filter_map(|status| status.error.as_ref()).collect()
+    }
 }
 
 impl StormReplay {
-    pub fn parse(archive: &mut Archive) -> ReplayResult<StormReplay> {
-        let mut replay: StormReplay = Default::default();
+    /// Parses every section of the replay contained in the given archive.
+    pub fn parse(archive: &mut ReplayArchive) -> ReplayResult<StormReplay> {
+        StormReplay::parse_with_options(archive, ParseOptions::new())
+    }
 
-        replay.parse_replay_metadata(archive)?;
-        replay.parse_replay_details(archive)?;
-        replay.parse_replay_init(archive)?;
-        replay.parse_replay_attributes(archive)?;
-        replay.parse_replay_game_events(archive)?;
-        replay.parse_replay_tracker_events(archive)?;
+    /// Parses the sections of the replay selected by `options`, leaving the rest at their defaults.
+    pub fn parse_with_options(archive: &mut ReplayArchive, options: ParseOptions) -> ReplayResult<StormReplay> {
+        StormReplay::parse_sections(archive, options, false).map(|(replay, _)| replay)
+    }
 
-        Ok(replay)
+    /// Parses the sections of the replay selected by `options`, carrying on past any that fail.
+    ///
+    /// Sections that fail are left at their defaults, or with whatever they had filled in by then, and their error
+    /// is reported alongside the replay.  Only a failure to read the replay header, which every other section depends on,
+    /// fails the parse as a whole.
+    pub fn parse_lenient(archive: &mut ReplayArchive, options: ParseOptions) -> ReplayResult<PartialReplay> {
+        StormReplay::parse_sections(archive, options, true)
+            .map(|(replay, sections)| PartialReplay { replay: replay, sections: sections })
     }
 
-    pub fn validate(archive: &mut Archive) -> ReplayResult<String> {
+    fn parse_sections(archive: &mut ReplayArchive, options: ParseOptions, lenient: bool) -> ReplayResult<(StormReplay, Vec<SectionStatus>)> {
         let mut replay: StormReplay = Default::default();
+        let mut sections: Vec<SectionStatus> = Vec::new();
 
         replay.parse_replay_metadata(archive)?;
-        replay.parse_replay_details(archive)?;
-        replay.parse_replay_init(archive)?;
+        sections.push(SectionStatus { section: ReplaySection::Header, complete: true, error: None });
+
+        if options.needs_details() {
+            sections.push(replay.parse_section(archive, ReplaySection::Details, lenient, StormReplay::parse_replay_details)?);
+        }
+        if options.init {
+            sections.push(replay.parse_section(archive, ReplaySection::Init, lenient, StormReplay::parse_replay_init)?);
+        }
+        if options.attributes {
+            sections.push(replay.parse_section(archive, ReplaySection::Attributes, lenient, StormReplay::parse_replay_attributes)?);
+        }
+        if options.battle_lobby {
+            sections.push(replay.parse_section(archive, ReplaySection::BattleLobby, lenient, StormReplay::parse_replay_battle_lobby)?);
+        }
+        if options.game_events {
+            sections.push(replay.parse_section(archive, ReplaySection::GameEvents, lenient, StormReplay::parse_replay_game_events)?);
+        }
+        if options.message_events {
+            sections.push(replay.parse_section(archive, ReplaySection::MessageEvents, lenient, StormReplay::parse_replay_message_events)?);
+        }
+        if options.tracker_events {
+            sections.push(replay.parse_section(archive, ReplaySection::TrackerEvents, lenient, StormReplay::parse_replay_tracker_events)?);
+        }
+
+        if options.tracker_events {
+            replay.parse_units();
+        }
+        replay.parse_identities();
+        if options.tracker_events {
+            replay.parse_draft();
+        }
+        if options.game_events || options.tracker_events {
+            replay.parse_talents();
+        }
+        if options.tracker_events {
+            replay.parse_scores();
+            replay.parse_levels();
+            replay.parse_deaths();
+            replay.parse_structures();
+            replay.parse_merc_camps();
+            replay.parse_objectives();
+            replay.parse_stat_counters();
+        }
+        if options.game_events {
+            replay.parse_disconnects();
+        }
+
+        Ok((replay, sections))
+    }
+
+    // Parses a single section.  A section that had to give up on anything along the way, which it notes in
+    // `warnings`, isn't complete either.
+    fn parse_section<F>(&mut self, archive: &mut ReplayArchive, section: ReplaySection, lenient: bool, parse: F) -> ReplayResult<SectionStatus>
+        where F: FnOnce(&mut StormReplay, &mut ReplayArchive) -> ReplayResult<()>
+    {
+        let warnings = self.warnings.len();
+        match parse(self, archive) {
+            Ok(()) => Ok(SectionStatus { section: section, complete: self.warnings.len() == warnings, error: None }),
+            Err(e) => if lenient {
+                Ok(SectionStatus { section: section, complete: false, error: Some(e) })
+            } else {
+                Err(e)
+            },
+        }
+    }
+
+    /// Parses the replay header, details and init data, and derives a signature that uniquely identifies
+    /// the match regardless of which player uploaded it.
+    pub fn validate(archive: &mut ReplayArchive) -> ReplayResult<String> {
+        let options = ParseOptions::header_only().details(true).init(true);
+        let replay = StormReplay::parse_with_options(archive, options)?;
 
         // We'll build a UUID from sha128(sorted player names + replay version + random value)
         let mut signature = String::new();
@@ -77,24 +209,25 @@ impl StormReplay {
             .map_err(|_| ReplayError::new(ReplayErrorKind::Other, "failed to generate signature for replay"))
     }
 
-    fn parse_replay_metadata(&mut self, archive: &mut Archive) -> ReplayResult<()> {
+    fn parse_replay_metadata(&mut self, archive: &mut ReplayArchive) -> ReplayResult<()> {
         match archive.read_user_data() {
             Ok(result) => match result {
                 Some(data) => {
                     let mut reader = BinaryReader::new(&data);
                     match TrackerEventStructure::new(&mut reader) {
                         Ok(event) => {
+                            let version = event.get_dict_entry(1)?;
                             let version_string = format!("{}.{}.{}.{}",
-                                event.get_dict_entry(1).get_dict_entry(0).get_vint(),
-                                event.get_dict_entry(1).get_dict_entry(1).get_vint(),
-                                event.get_dict_entry(1).get_dict_entry(2).get_vint(),
-                                event.get_dict_entry(1).get_dict_entry(3).get_vint());
+                                version.get_dict_entry(0)?.get_vint()?,
+                                version.get_dict_entry(1)?.get_vint()?,
+                                version.get_dict_entry(2)?.get_vint()?,
+                                version.get_dict_entry(3)?.get_vint()?);
 
                             self.replay_version = version_string;
-                            self.replay_build = event.get_dict_entry(1).get_dict_entry(4).get_vint() as u32;
+                            self.replay_build = version.get_dict_entry(4)?.get_vint()? as u32;
 
                             if self.replay_build >= 51978 {
-                                self.replay_version_major = event.get_dict_entry(1).get_dict_entry(1).get_vint() as u32;
+                                self.replay_version_major = version.get_dict_entry(1)?.get_vint()? as u32;
                             } else {
                                 self.replay_version_major = 1;
                             }
@@ -102,12 +235,12 @@ impl StormReplay {
                             if self.replay_build >= 39951 {
                                 // As noted by barrett777, this build number seems to be a more accurate build number,
                                 // and was noticed as changing after build 39951.
-                                self.replay_build = event.get_dict_entry(6).get_vint() as u32;
+                                self.replay_build = event.get_dict_entry(6)?.get_vint()? as u32;
                             }
 
                             // The SC2/HoTS game engine runs at 16 frames per second, so it tracks the match length in
                             // frames, and we do some simple math here fto get the real time.
-                            self.frames = event.get_dict_entry(3).get_vint() as u32;
+                            self.frames = event.get_dict_entry(3)?.get_vint()? as u32;
                             self.game_length_sec = self.frames / 16;
 
                             Ok(())
@@ -121,30 +254,135 @@ impl StormReplay {
         }
     }
 
-    fn parse_replay_details(&mut self, archive: &mut Archive) -> ReplayResult<()> {
+    fn parse_replay_details(&mut self, archive: &mut ReplayArchive) -> ReplayResult<()> {
         ReplayDetails::parse_replay_details(self, archive)
     }
 
-    fn parse_replay_init(&mut self, archive: &mut Archive) -> ReplayResult<()> {
+    fn parse_replay_init(&mut self, archive: &mut ReplayArchive) -> ReplayResult<()> {
         ReplayInit::parse_replay_init(self, archive)
     }
 
-    fn parse_replay_attributes(&mut self, archive: &mut Archive) -> ReplayResult<()> {
+    fn parse_replay_attributes(&mut self, archive: &mut ReplayArchive) -> ReplayResult<()> {
         ReplayAttributes::parse_replay_attributes(self, archive)
     }
 
-    fn parse_replay_game_events(&mut self, archive: &mut Archive) -> ReplayResult<()> {
+    fn parse_replay_battle_lobby(&mut self, archive: &mut ReplayArchive) -> ReplayResult<()> {
+        ReplayBattleLobby::parse_replay_battle_lobby(self, archive)
+    }
+
+    fn parse_replay_game_events(&mut self, archive: &mut ReplayArchive) -> ReplayResult<()> {
         ReplayGameEvents::parse_replay_game_events(self, archive)
     }
 
-    fn parse_replay_tracker_events(&mut self, archive: &mut Archive) -> ReplayResult<()> {
+    fn parse_replay_message_events(&mut self, archive: &mut ReplayArchive) -> ReplayResult<()> {
+        ReplayMessageEvents::parse_replay_message_events(self, archive)
+    }
+
+    fn parse_replay_tracker_events(&mut self, archive: &mut ReplayArchive) -> ReplayResult<()> {
         ReplayTrackerEvents::parse_replay_tracker_events(self, archive)
     }
 
+    fn parse_units(&mut self) {
+        ReplayUnits::parse_units(self)
+    }
+
+    fn parse_identities(&mut self) {
+        ReplayIdentities::parse_identities(self)
+    }
+
+    fn parse_draft(&mut self) {
+        ReplayDraft::parse_draft(self)
+    }
+
+    fn parse_talents(&mut self) {
+        ReplayTalents::parse_talents(self)
+    }
+
+    fn parse_scores(&mut self) {
+        ReplayScores::parse_scores(self)
+    }
+
+    fn parse_levels(&mut self) {
+        ReplayLevels::parse_levels(self)
+    }
+
+    fn parse_deaths(&mut self) {
+        ReplayDeaths::parse_deaths(self)
+    }
+
+    fn parse_structures(&mut self) {
+        ReplayStructures::parse_structures(self)
+    }
+
+    fn parse_merc_camps(&mut self) {
+        ReplayMercCamps::parse_merc_camps(self)
+    }
+
+    fn parse_objectives(&mut self) {
+        ReplayObjectives::parse_objectives(self)
+    }
+
+    fn parse_stat_counters(&mut self) {
+        ReplayStatCounters::parse_stat_counters(self)
+    }
+
+    fn parse_disconnects(&mut self) {
+        ReplayDisconnects::parse_disconnects(self)
+    }
+
+    /// Looks up a player by its index in `replay.details`.
     pub fn get_player_by_index(&mut self, index: u32) -> Option<&mut Player> {
         self.players.iter_mut().find(|ref p| p.index == index)
     }
 
+    /// Looks up how the player at the given position in `players` is known to each section of the replay.
+    pub fn get_player_identity(&self, position: usize) -> Option<&PlayerIdentity> {
+        self.player_identities.get(position)
+    }
+
+    /// Finds the position in `players` of the player with the given working set slot id.
+    pub fn get_player_position_by_slot_id(&self, slot_id: u32) -> Option<usize> {
+        self.find_player_position(|identity| identity.slot_id == slot_id)
+    }
+
+    /// Finds the position in `players` of the player with the given one-based attribute player id.
+    pub fn get_player_position_by_attribute_id(&self, player_id: u32) -> Option<usize> {
+        self.find_player_position(|identity| identity.attribute_player_id == Some(player_id))
+    }
+
+    /// Finds the position in `players` of the player with the given user id, as used by the game and
+    /// message events.
+    pub fn get_player_position_by_user_id(&self, user_id: u32) -> Option<usize> {
+        self.find_player_position(|identity| identity.user_id == Some(user_id))
+    }
+
+    /// Finds the position in `players` of the player with the given one-based tracker player id.
+    pub fn get_player_position_by_tracker_id(&self, player_id: u32) -> Option<usize> {
+        self.find_player_position(|identity| identity.tracker_player_id == player_id)
+    }
+
+    /// Finds the position in `players` of the player whose hero has the given unit tag.
+    pub fn get_player_position_by_hero_unit_tag(&self, unit_tag: u32) -> Option<usize> {
+        self.find_player_position(|identity| identity.hero_unit_tag == Some(unit_tag))
+    }
+
+    /// Finds the position in `players` of the player who issued the given game event.
+    pub fn get_game_event_player(&self, event: &GameEvent) -> Option<usize> {
+        event.player.and_then(|user_id| self.get_player_position_by_user_id(user_id))
+    }
+
+    /// Finds the position in `players` of the player who sent the given message event.
+    pub fn get_message_event_player(&self, event: &MessageEvent) -> Option<usize> {
+        event.player.and_then(|user_id| self.get_player_position_by_user_id(user_id))
+    }
+
+    fn find_player_position<F>(&self, predicate: F) -> Option<usize>
+        where F: Fn(&PlayerIdentity) -> bool
+    {
+        self.player_identities.iter().find(|identity| predicate(identity)).map(|identity| identity.position)
+    }
+
+    /// Serializes the replay to JSON.
     pub fn to_json(&self) -> ReplayResult<String> {
         match serde_json::to_string(self) {
             Ok(s) => Ok(s),
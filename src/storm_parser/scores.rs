@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use storm_parser::replay::StormReplay;
+use storm_parser::tracker::TrackerEventKind;
+
+/// A player's numbers from the end-of-game score screen.
+///
+/// Scores that the replay's build doesn't report are left at zero.  Any score we don't have a field for
+/// ends up in `other`, keyed by the name the game gives it.
+#[derive(Serialize, Clone, Default, Debug)]
+pub struct PlayerScore {
+    pub level: i64,
+    pub takedowns: i64,
+    pub solo_kills: i64,
+    pub assists: i64,
+    pub deaths: i64,
+    pub highest_kill_streak: i64,
+    pub hero_damage: i64,
+    pub siege_damage: i64,
+    pub structure_damage: i64,
+    pub minion_damage: i64,
+    pub creep_damage: i64,
+    pub summon_damage: i64,
+    pub damage_taken: i64,
+    pub healing: i64,
+    pub self_healing: i64,
+    pub protection_given_to_allies: i64,
+    pub experience_contribution: i64,
+    pub town_kills: i64,
+    pub merc_camp_captures: i64,
+    pub watch_tower_captures: i64,
+    pub meta_experience: i64,
+    // All times are in seconds.
+    pub time_spent_dead: i64,
+    pub time_cc_enemy_heroes: i64,
+    pub time_stunning_enemy_heroes: i64,
+    pub time_rooting_enemy_heroes: i64,
+    pub time_silencing_enemy_heroes: i64,
+    pub teamfight_hero_damage: i64,
+    pub teamfight_damage_taken: i64,
+    pub teamfight_healing_done: i64,
+    pub escapes_performed: i64,
+    pub vengeances_performed: i64,
+    pub outnumbered_deaths: i64,
+    pub clutch_heals_performed: i64,
+    pub other: HashMap<String, i64>,
+}
+
+impl PlayerScore {
+    /// Sets the score with the given name, as it appears in the score result event.
+    pub fn set(&mut self, name: &str, value: i64) {
+        match name {
+            "Level" => self.level = value,
+            "Takedowns" => self.takedowns = value,
+            "SoloKill" => self.solo_kills = value,
+            "Assists" => self.assists = value,
+            "Deaths" => self.deaths = value,
+            "HighestKillStreak" => self.highest_kill_streak = value,
+            "HeroDamage" => self.hero_damage = value,
+            "SiegeDamage" => self.siege_damage = value,
+            "StructureDamage" => self.structure_damage = value,
+            "MinionDamage" => self.minion_damage = value,
+            "CreepDamage" => self.creep_damage = value,
+            "SummonDamage" => self.summon_damage = value,
+            "DamageTaken" => self.damage_taken = value,
+            "Healing" => self.healing = value,
+            "SelfHealing" => self.self_healing = value,
+            "ProtectionGivenToAllies" => self.protection_given_to_allies = value,
+            "ExperienceContribution" => self.experience_contribution = value,
+            "TownKills" => self.town_kills = value,
+            "MercCampCaptures" => self.merc_camp_captures = value,
+            "WatchTowerCaptures" => self.watch_tower_captures = value,
+            "MetaExperience" => self.meta_experience = value,
+            "TimeSpentDead" => self.time_spent_dead = value,
+            "TimeCCdEnemyHeroes" => self.time_cc_enemy_heroes = value,
+            "TimeStunningEnemyHeroes" => self.time_stunning_enemy_heroes = value,
+            "TimeRootingEnemyHeroes" => self.time_rooting_enemy_heroes = value,
+            "TimeSilencingEnemyHeroes" => self.time_silencing_enemy_heroes = value,
+            "TeamfightHeroDamage" => self.teamfight_hero_damage = value,
+            "TeamfightDamageTaken" => self.teamfight_damage_taken = value,
+            "TeamfightHealingDone" => self.teamfight_healing_done = value,
+            "EscapesPerformed" => self.escapes_performed = value,
+            "VengeancesPerformed" => self.vengeances_performed = value,
+            "OutnumberedDeaths" => self.outnumbered_deaths = value,
+            "ClutchHealsPerformed" => self.clutch_heals_performed = value,
+            _ => { self.other.insert(name.to_string(), value); }
+        }
+    }
+}
+
+pub struct ReplayScores {
+}
+
+impl ReplayScores {
+    /// Fills in each player's score screen from the `ScoreResultEvent` sent at the end of the game.
+    ///
+    /// Replays of games that didn't run to completion have no such event, and their players are left
+    /// without a score.
+    pub fn parse_scores(replay: &mut StormReplay) {
+        let mut scores: Vec<Option<PlayerScore>> = replay.players.iter().map(|_| None).collect();
+
+        // There should only be the one, but should there be more, the last word counts.
+        let score_result = replay.tracker_events.iter().rev()
+            .filter_map(|event| match event.kind {
+                TrackerEventKind::ScoreResult(ref score_result) => Some(score_result),
+                _ => None
+            })
+            .next();
+
+        if let Some(score_result) = score_result {
+            for instance in score_result.instances.iter() {
+                // Each score has a slot for every possible player, indexed by tracker player id, and most of them
+                // are empty.  A slot holds the history of the value, of which we want the final one.
+                for (slot, values) in instance.values.iter().enumerate() {
+                    let value = match values.last() {
+                        Some(value) => value.value,
+                        None => continue,
+                    };
+
+                    if let Some(position) = replay.get_player_position_by_tracker_id(slot as u32 + 1) {
+                        scores[position].get_or_insert_with(Default::default).set(&instance.name, value);
+                    }
+                }
+            }
+        }
+
+        for (player, score) in replay.players.iter_mut().zip(scores.into_iter()) {
+            player.score = score;
+        }
+    }
+}
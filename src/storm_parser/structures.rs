@@ -0,0 +1,223 @@
+use storm_parser::replay::StormReplay;
+use storm_parser::tracker::TrackerEventKind;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+pub enum StructureKind {
+    Fort,
+    Keep,
+    Tower,
+    Well,
+    Gate,
+    Wall,
+    Core,
+}
+
+/// The structures destroyed over the course of the game.
+#[derive(Serialize, Default, Debug)]
+pub struct Structures {
+    pub destroyed: Vec<DestroyedStructure>,
+    // Indices into `destroyed` of the first structure of each kind to fall.
+    pub first_tower: Option<usize>,
+    pub first_fort: Option<usize>,
+    pub first_keep: Option<usize>,
+}
+
+impl Structures {
+    /// The structures of the given kind destroyed by the given team, in order.
+    pub fn destroyed_by(&self, team: u32, kind: StructureKind) -> Vec<&DestroyedStructure> {
+        self.destroyed.iter().filter(|s| s.destroying_team == Some(team) && s.kind == kind).collect()
+    }
+}
+
+/// A structure's destruction.
+///
+/// Players are referred to by their position in `StormReplay::players`.
+#[derive(Serialize, Clone, Debug)]
+pub struct DestroyedStructure {
+    pub unit_tag: u32,
+    pub unit_type_name: String,
+    pub kind: StructureKind,
+    // One-based, as numbered by the map, for structures that belong to one of the towns along a lane.
+    pub lane: Option<u32>,
+    pub x: u32,
+    pub y: u32,
+    pub team: Option<u32>,
+    pub destroying_team: Option<u32>,
+    // The heroes credited with the kill.  Structures often fall to minions and mercenaries, leaving this empty.
+    pub killers: Vec<usize>,
+    pub ticks_elapsed: u32,
+    pub time_sec: u32,
+}
+
+// How far a structure can be from the center of its town, in map units.
+const MAX_TOWN_DISTANCE: f64 = 25.0;
+// How far behind a structure's UnitDied event its TownStructureDeath stat event may be, in ticks.
+const MAX_DEATH_EVENT_LAG: u32 = 16;
+
+// A town, as announced at the start of the game.
+struct Town {
+    team: u32,
+    lane: u32,
+    x: f64,
+    y: f64,
+}
+
+// A structure's death, as announced by the stat events.
+struct TownStructureDeath {
+    killers: Vec<usize>,
+    x: f64,
+    y: f64,
+    ticks_elapsed: u32,
+}
+
+pub struct ReplayStructures {
+}
+
+impl ReplayStructures {
+    /// Builds the timeline of destroyed structures from the unit registry.
+    ///
+    /// Structures belong to the AI players of their team, and the unit events only credit whoever landed the
+    /// killing blow, which is as often a minion as a hero.  Towns are announced with their lane when the game
+    /// starts, so we place each structure in the town nearest to it.
+    pub fn parse_structures(replay: &mut StormReplay) {
+        let mut towns: Vec<Town> = Vec::new();
+        let mut town_deaths: Vec<TownStructureDeath> = Vec::new();
+
+        for event in replay.tracker_events.iter() {
+            let stat = match event.kind {
+                TrackerEventKind::StatGame(ref stat) => stat,
+                _ => continue,
+            };
+
+            match stat.name.as_ref() {
+                "TownStructureInit" => {
+                    // These teams are one-based.
+                    let team = stat.get_int("Team").and_then(|team| team.checked_sub(1));
+                    if let (Some(team), Some(lane)) = (team, stat.get_int("Lane")) {
+                        towns.push(Town {
+                            team: team as u32,
+                            lane: lane as u32,
+                            x: stat.get_fixed("PositionX").unwrap_or(0.0),
+                            y: stat.get_fixed("PositionY").unwrap_or(0.0),
+                        });
+                    }
+                },
+                "TownStructureDeath" => {
+                    town_deaths.push(TownStructureDeath {
+                        killers: stat.get_ints("KillingPlayer").iter()
+                            .filter_map(|&player_id| replay.get_player_position_by_tracker_id(player_id as u32))
+                            .collect(),
+                        x: stat.get_fixed("PositionX").unwrap_or(0.0),
+                        y: stat.get_fixed("PositionY").unwrap_or(0.0),
+                        ticks_elapsed: event.ticks_elapsed,
+                    });
+                },
+                _ => {}
+            }
+        }
+
+        let mut structures: Structures = Default::default();
+
+        for unit in replay.units.units.iter() {
+            let death = match unit.deaths.first() {
+                Some(death) => death,
+                None => continue,
+            };
+
+            // Towers turn into husks as they die, so look for the last type that was still a structure.
+            let structure_type = unit.types.iter()
+                .take_while(|entry| entry.ticks_elapsed <= death.ticks_elapsed)
+                .filter_map(|entry| get_structure_kind(&entry.unit_type_name).map(|kind| (kind, &entry.unit_type_name)))
+                .last();
+            let (kind, unit_type_name) = match structure_type {
+                Some(structure_type) => structure_type,
+                None => continue,
+            };
+
+            let (x, y) = match unit.position_at(death.ticks_elapsed) {
+                Some(position) => (position.x, position.y),
+                None => (0, 0),
+            };
+
+            // The AI players that own structures are numbered 11 and 12.
+            let team = match unit.owner_at(death.ticks_elapsed).map(|owner| owner.control_player_id) {
+                Some(11) => Some(0),
+                Some(12) => Some(1),
+                _ => None,
+            };
+
+            let lane = match (kind, team) {
+                (StructureKind::Core, _) | (_, None) => None,
+                (_, Some(team)) => towns.iter()
+                    .filter(|town| town.team == team)
+                    .map(|town| (town.lane, distance(town.x, town.y, x as f64, y as f64)))
+                    .filter(|&(_, distance)| distance <= MAX_TOWN_DISTANCE)
+                    .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(::std::cmp::Ordering::Equal))
+                    .map(|(lane, _)| lane),
+            };
+
+            let mut killers: Vec<usize> = death.killer_player_id
+                .and_then(|player_id| replay.get_player_position_by_tracker_id(player_id))
+                .into_iter()
+                .collect();
+
+            let town_death = town_deaths.iter().find(|town_death| {
+                town_death.ticks_elapsed >= death.ticks_elapsed &&
+                    town_death.ticks_elapsed - death.ticks_elapsed <= MAX_DEATH_EVENT_LAG &&
+                    distance(town_death.x, town_death.y, x as f64, y as f64) < 2.0
+            });
+            if let Some(town_death) = town_death {
+                for killer in town_death.killers.iter() {
+                    if !killers.contains(killer) {
+                        killers.push(*killer);
+                    }
+                }
+            }
+
+            structures.destroyed.push(DestroyedStructure {
+                unit_tag: unit.unit_tag,
+                unit_type_name: unit_type_name.clone(),
+                kind: kind,
+                lane: lane,
+                x: x,
+                y: y,
+                team: team,
+                destroying_team: team.map(|team| 1 - team),
+                killers: killers,
+                ticks_elapsed: death.ticks_elapsed,
+                time_sec: death.ticks_elapsed / 16,
+            });
+        }
+
+        structures.destroyed.sort_by_key(|structure| structure.ticks_elapsed);
+        structures.first_tower = structures.destroyed.iter().position(|s| s.kind == StructureKind::Tower);
+        structures.first_fort = structures.destroyed.iter().position(|s| s.kind == StructureKind::Fort);
+        structures.first_keep = structures.destroyed.iter().position(|s| s.kind == StructureKind::Keep);
+
+        replay.structures = structures;
+    }
+}
+
+fn get_structure_kind(unit_type_name: &str) -> Option<StructureKind> {
+    if unit_type_name.starts_with("TownTownHallL2") {
+        Some(StructureKind::Fort)
+    } else if unit_type_name.starts_with("TownTownHallL3") {
+        Some(StructureKind::Keep)
+    } else if unit_type_name.starts_with("TownCannonTower") && !unit_type_name.ends_with("Dead") {
+        Some(StructureKind::Tower)
+    } else if unit_type_name.starts_with("TownMoonwell") {
+        Some(StructureKind::Well)
+    } else if unit_type_name.starts_with("TownGate") {
+        Some(StructureKind::Gate)
+    } else if unit_type_name.starts_with("TownWall") {
+        Some(StructureKind::Wall)
+    } else if unit_type_name == "KingsCore" || unit_type_name == "VanndarStormpike" || unit_type_name == "DrekThar" {
+        Some(StructureKind::Core)
+    } else {
+        None
+    }
+}
+
+fn distance(x1: f64, y1: f64, x2: f64, y2: f64) -> f64 {
+    ((x1 - x2).powi(2) + (y1 - y2).powi(2)).sqrt()
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use storm_parser::replay::StormReplay;
+use storm_parser::events::GameEventKind;
+use storm_parser::tracker::TrackerEventKind;
+
+/// A talent picked by a player.
+#[derive(Serialize, Clone, Debug)]
+pub struct TalentChoice {
+    // One-based, in the order the talents were picked.
+    pub tier: u32,
+    // Position of the talent in the hero's talent tree, when the game events were parsed.
+    pub index: Option<u32>,
+    // Internal name of the talent, when the tracker events were parsed.
+    pub name: Option<String>,
+    pub ticks_elapsed: u32,
+    pub time_sec: u32,
+}
+
+// A talent pick as seen by one of the two event streams.
+struct TalentPick {
+    index: Option<u32>,
+    name: Option<String>,
+    ticks_elapsed: u32,
+}
+
+pub struct ReplayTalents {
+}
+
+impl ReplayTalents {
+    /// Fills in the talents picked by each player.
+    ///
+    /// The game events tell us when each talent was picked and where it sits in the talent tree, while the
+    /// tracker events name it: once as it's picked, and once more for the whole build at the end of the game.
+    /// Either stream is enough on its own, and we merge the two when both were parsed.
+    pub fn parse_talents(replay: &mut StormReplay) {
+        let player_count = replay.players.len();
+        let mut selected: Vec<Vec<TalentPick>> = (0..player_count).map(|_| Vec::new()).collect();
+        let mut chosen: Vec<Vec<TalentPick>> = (0..player_count).map(|_| Vec::new()).collect();
+        let mut final_choices: Vec<HashMap<u32, String>> = (0..player_count).map(|_| HashMap::new()).collect();
+
+        for event in replay.game_events.iter() {
+            if let GameEventKind::HeroTalentSelected(ref talent) = event.kind {
+                let position = replay.get_game_event_player(event);
+                if let Some(position) = position {
+                    selected[position].push(TalentPick {
+                        index: Some(talent.index),
+                        name: None,
+                        ticks_elapsed: event.ticks_elapsed,
+                    });
+                }
+            }
+        }
+
+        for event in replay.tracker_events.iter() {
+            if let TrackerEventKind::StatGame(ref stat) = event.kind {
+                let position = stat.get_int("PlayerID")
+                    .and_then(|player_id| replay.get_player_position_by_tracker_id(player_id as u32));
+                let position = match position {
+                    Some(position) => position,
+                    None => continue,
+                };
+
+                if stat.name == "TalentChosen" {
+                    chosen[position].push(TalentPick {
+                        index: None,
+                        name: stat.get_string("PurchaseName").map(|name| name.to_string()),
+                        ticks_elapsed: event.ticks_elapsed,
+                    });
+                } else if stat.name == "EndOfGameTalentChoices" {
+                    for (key, values) in stat.strings.iter() {
+                        if let (Some(tier), Some(value)) = (parse_tier_key(key), values.first()) {
+                            final_choices[position].insert(tier, value.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        for (position, player) in replay.players.iter_mut().enumerate() {
+            // Prefer the game events for timing, since they carry the talent index as well.
+            let picks = if selected[position].len() > 0 { &selected[position] } else { &chosen[position] };
+
+            player.talents = picks.iter().enumerate().map(|(i, pick)| {
+                let tier = i as u32 + 1;
+                let name = final_choices[position].get(&tier).cloned()
+                    .or_else(|| pick.name.clone())
+                    .or_else(|| chosen[position].get(i).and_then(|c| c.name.clone()));
+
+                TalentChoice {
+                    tier: tier,
+                    index: pick.index,
+                    name: name,
+                    ticks_elapsed: pick.ticks_elapsed,
+                    time_sec: pick.ticks_elapsed / 16,
+                }
+            }).collect();
+        }
+    }
+}
+
+// Parses keys of the form "Tier 3 Choice".
+fn parse_tier_key(key: &str) -> Option<u32> {
+    if key.starts_with("Tier ") && key.ends_with(" Choice") && key.len() > 12 {
+        key[5..key.len() - 7].parse().ok()
+    } else {
+        None
+    }
+}
@@ -2,13 +2,15 @@ use std::io;
 use std::borrow::BorrowMut;
 use std::collections::HashMap;
 
-use mpq::Archive;
 use num_traits::FromPrimitive;
 
+use storm_parser::archive::ReplayArchive;
 use storm_parser::replay::StormReplay;
 use storm_parser::binary_reader::BinaryReader;
 use storm_parser::primitives::*;
 
+/// A value in the self-describing versioned encoding used by the tracker events, replay details and
+/// replay header.
 #[derive(Serialize, Clone, Debug, Default)]
 pub struct TrackerEventStructure {
     pub(crate) data_type: u32,
@@ -30,8 +32,20 @@ pub struct TrackerEventStructure {
     pub(crate) variable_int: Option<i64>,
 }
 
+// How deeply structures may nest.  Real replays stay in the single digits, so anything past this is garbage,
+// and following it any further would only run us out of stack.
+const MAX_STRUCTURE_DEPTH: u32 = 64;
+
 impl TrackerEventStructure {
     pub fn new(r: &mut BinaryReader) -> ReplayResult<TrackerEventStructure> {
+        TrackerEventStructure::read(r, 0)
+    }
+
+    fn read(r: &mut BinaryReader, depth: u32) -> ReplayResult<TrackerEventStructure> {
+        if depth > MAX_STRUCTURE_DEPTH {
+            return Err(ReplayError::new(ReplayErrorKind::StructureError, "tracker event structure nested too deeply"));
+        }
+
         let mut event: TrackerEventStructure = Default::default();
 
         event.data_type = r.read_u8()? as u32;
@@ -40,13 +54,20 @@ impl TrackerEventStructure {
                 let array_len = read_variable_int(r)?;
                 let mut array: Vec<TrackerEventStructure> = Vec::new();
                 for _ in 0..array_len {
-                    let event = TrackerEventStructure::new(r)?;
+                    let event = TrackerEventStructure::read(r, depth + 1)?;
                     array.push(event);
                 }
 
                 event.array = array;
             },
-            0x01 => panic!("unsupported tracker event type"),
+            0x01 => {
+                // A bit array: its length in bits, then as many bytes as that takes.
+                let bit_len = read_variable_int(r)?;
+                let buf = r.read_bytes(((bit_len + 7) / 8) as u32)?;
+
+                event.unsigned_int = Some(bit_len as u64);
+                event.blob = Some(buf);
+            },
             0x02 => {
                 let blob_len = read_variable_int(r)?;
                 let buf = r.read_bytes(blob_len as u32)?;
@@ -55,7 +76,7 @@ impl TrackerEventStructure {
             },
             0x03 => {
                 let choice_flag = read_variable_int(r)? as i32;
-                let choice_data = TrackerEventStructure::new(r)?;
+                let choice_data = TrackerEventStructure::read(r, depth + 1)?;
 
                 event.choice_flag = Some(choice_flag);
                 event.choice_data = Some(Box::new(choice_data));
@@ -63,7 +84,7 @@ impl TrackerEventStructure {
             0x04 => {
                 let should_read = r.read_u8()?;
                 if should_read != 0 {
-                    let optional_data = TrackerEventStructure::new(r)?;
+                    let optional_data = TrackerEventStructure::read(r, depth + 1)?;
                     event.optional_data = Some(Box::new(optional_data));
                 }
             },
@@ -74,7 +95,7 @@ impl TrackerEventStructure {
                 let dictionary_len = read_variable_int(r)?;
                 for _ in 0..dictionary_len {
                     let key = read_variable_int(r)? as i32;
-                    let value = TrackerEventStructure::new(r)?;
+                    let value = TrackerEventStructure::read(r, depth + 1)?;
 
                     dictionary.insert(key, value);
                 }
@@ -93,7 +114,8 @@ impl TrackerEventStructure {
             0x09 => {
                 event.variable_int = Some(read_variable_int(r)?);
             },
-            _ => panic!("unsupported tracker event type")
+            data_type => return Err(ReplayError::new(ReplayErrorKind::StructureError,
+                &format!("unknown tracker event data type '{}'", data_type)))
         }
 
         Ok(event)
@@ -111,45 +133,65 @@ impl TrackerEventStructure {
         &self.dictionary
     }
 
-    pub fn get_dict_entry(&self, index: i32) -> &TrackerEventStructure {
-        self.dictionary.get(&index).unwrap()
+    pub fn get_dict_entry(&self, index: i32) -> ReplayResult<&TrackerEventStructure> {
+        self.dictionary.get(&index).ok_or_else(|| missing_value(&format!("dictionary entry {}", index)))
     }
 
-    pub fn get_mut_dict_entry(&mut self, index: i32) -> &mut TrackerEventStructure {
-        self.dictionary.get_mut(&index).unwrap()
+    pub fn get_mut_dict_entry(&mut self, index: i32) -> ReplayResult<&mut TrackerEventStructure> {
+        self.dictionary.get_mut(&index).ok_or_else(|| missing_value(&format!("dictionary entry {}", index)))
     }
 
-    pub fn get_blob(&self) -> &Vec<u8> {
-        self.blob.as_ref().unwrap().as_ref()
+    pub fn get_blob(&self) -> ReplayResult<&Vec<u8>> {
+        self.blob.as_ref().ok_or_else(|| missing_value("blob"))
     }
-    pub fn get_blob_text(&self) -> String {
-        let blob = self.get_blob().clone();
-        String::from_utf8(blob).unwrap()
+
+    pub fn get_blob_text(&self) -> ReplayResult<String> {
+        self.get_blob().map(|blob| String::from_utf8_lossy(blob).into_owned())
+    }
+
+    pub fn get_choice_flag(&self) -> ReplayResult<i32> {
+        self.choice_flag.ok_or_else(|| missing_value("choice flag"))
     }
 
-    pub fn get_choice_flag(&self) -> i32 {
-        self.choice_flag.unwrap()
+    pub fn get_choice_data(&self) -> ReplayResult<&TrackerEventStructure> {
+        self.choice_data.as_ref().map(|data| data.as_ref()).ok_or_else(|| missing_value("choice data"))
     }
 
-    pub fn get_choice_data(&self) -> &TrackerEventStructure {
-        self.choice_data.as_ref().unwrap().as_ref()
+    pub fn get_optional_data(&self) -> ReplayResult<&TrackerEventStructure> {
+        self.optional_data.as_ref().map(|data| data.as_ref()).ok_or_else(|| missing_value("optional data"))
     }
 
-    pub fn get_optional_data(&self) -> &TrackerEventStructure {
-        self.optional_data.as_ref().unwrap().as_ref()
+    pub fn get_mut_optional_data(&mut self) -> ReplayResult<&mut TrackerEventStructure> {
+        self.optional_data.as_mut().map(|data| data.borrow_mut()).ok_or_else(|| missing_value("optional data"))
     }
 
-    pub fn get_mut_optional_data(&mut self) -> &mut TrackerEventStructure {
-        self.optional_data.as_mut().unwrap().borrow_mut()
+    pub fn get_uint(&self) -> ReplayResult<u64> {
+        self.unsigned_int.ok_or_else(|| missing_value("unsigned int"))
     }
 
-    pub fn get_uint(&self) -> u64 {
-        self.unsigned_int.unwrap()
+    pub fn get_vint(&self) -> ReplayResult<i64> {
+        self.variable_int.ok_or_else(|| missing_value("variable int"))
     }
 
-    pub fn get_vint(&self) -> i64 {
-        self.variable_int.unwrap()
+    pub(crate) fn try_get_dict_entry(&self, index: i32) -> Option<&TrackerEventStructure> {
+        self.dictionary.get(&index)
     }
+
+    pub(crate) fn try_get_optional_data(&self) -> Option<&TrackerEventStructure> {
+        self.optional_data.as_ref().map(|data| data.as_ref())
+    }
+
+    pub(crate) fn try_get_blob_text(&self) -> Option<String> {
+        self.blob.as_ref().and_then(|blob| String::from_utf8(blob.clone()).ok())
+    }
+
+    pub(crate) fn try_get_vint(&self) -> Option<i64> {
+        self.variable_int
+    }
+}
+
+fn missing_value(what: &str) -> ReplayError {
+    ReplayError::new(ReplayErrorKind::StructureError, &format!("tracker event structure has no {}", what))
 }
 
 fn read_variable_int(r: &mut BinaryReader) -> Result<i64, io::Error> {
@@ -165,6 +207,9 @@ fn read_variable_int(r: &mut BinaryReader) -> Result<i64, io::Error> {
         }
 
         k = k + 7;
+        if k >= 64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "variable int too long"));
+        }
     }
 
      if x & 1 > 0 {
@@ -174,73 +219,563 @@ fn read_variable_int(r: &mut BinaryReader) -> Result<i64, io::Error> {
     }
 }
 
-#[derive(Serialize, Clone, Debug, Default)]
+/// A single event from `replay.tracker.events`, i.e. a change in game state.
+///
+/// Players are referred to by their one-based tracker player ids, as the replay has them.
+/// `StormReplay::get_player_position_by_tracker_id` finds their position in `StormReplay::players`.
+#[derive(Serialize, Clone, Debug)]
 pub struct TrackerEvent {
     pub event_type: ReplayTrackerEventType,
     pub ticks_elapsed: u32,
-    pub data: TrackerEventStructure
+    pub kind: TrackerEventKind,
+}
+
+/// The payload of a `TrackerEvent`.
+///
+/// Events of a type we don't know, or whose structure doesn't match the layout we expect for their type, are
+/// kept in their raw form as `Unknown`, so nothing that was in the replay is lost.
+#[derive(Serialize, Clone, Debug)]
+pub enum TrackerEventKind {
+    UnitBorn(UnitBornEvent),
+    UnitDied(UnitDiedEvent),
+    UnitOwnerChange(UnitOwnerChangeEvent),
+    UnitTypeChange(UnitTypeChangeEvent),
+    Upgrade(UpgradeEvent),
+    UnitInit(UnitBornEvent),
+    UnitDone(UnitDoneEvent),
+    UnitPositions(UnitPositionsEvent),
+    PlayerSetup(PlayerSetupEvent),
+    StatGame(StatGameEvent),
+    ScoreResult(ScoreResultEvent),
+    UnitRevived(UnitRevivedEvent),
+    HeroBanned(HeroBannedEvent),
+    HeroPicked(HeroPickedEvent),
+    HeroSwapped(HeroSwappedEvent),
+    Unknown {
+        id: u32,
+        data: TrackerEventStructure,
+    },
+}
+
+impl TrackerEventKind {
+    fn from_structure(event_type: ReplayTrackerEventType, data: &TrackerEventStructure) -> Option<TrackerEventKind> {
+        match event_type {
+            ReplayTrackerEventType::UnitBornEvent => UnitBornEvent::from_structure(data).map(TrackerEventKind::UnitBorn),
+            ReplayTrackerEventType::UnitDiedEvent => UnitDiedEvent::from_structure(data).map(TrackerEventKind::UnitDied),
+            ReplayTrackerEventType::UnitOwnerChangeEvent => UnitOwnerChangeEvent::from_structure(data).map(TrackerEventKind::UnitOwnerChange),
+            ReplayTrackerEventType::UnitTypeChangeEvent => UnitTypeChangeEvent::from_structure(data).map(TrackerEventKind::UnitTypeChange),
+            ReplayTrackerEventType::UpgradeEvent => UpgradeEvent::from_structure(data).map(TrackerEventKind::Upgrade),
+            ReplayTrackerEventType::UnitInitEvent => UnitBornEvent::from_structure(data).map(TrackerEventKind::UnitInit),
+            ReplayTrackerEventType::UnitDoneEvent => UnitDoneEvent::from_structure(data).map(TrackerEventKind::UnitDone),
+            ReplayTrackerEventType::UnitPositionsEvent => UnitPositionsEvent::from_structure(data).map(TrackerEventKind::UnitPositions),
+            ReplayTrackerEventType::PlayerSetupEvent => PlayerSetupEvent::from_structure(data).map(TrackerEventKind::PlayerSetup),
+            ReplayTrackerEventType::StatGameEvent => StatGameEvent::from_structure(data).map(TrackerEventKind::StatGame),
+            ReplayTrackerEventType::ScoreResultEvent => ScoreResultEvent::from_structure(data).map(TrackerEventKind::ScoreResult),
+            ReplayTrackerEventType::UnitRevivedEvent => UnitRevivedEvent::from_structure(data).map(TrackerEventKind::UnitRevived),
+            ReplayTrackerEventType::HeroBannedEvent => HeroBannedEvent::from_structure(data).map(TrackerEventKind::HeroBanned),
+            ReplayTrackerEventType::HeroPickedEvent => HeroPickedEvent::from_structure(data).map(TrackerEventKind::HeroPicked),
+            ReplayTrackerEventType::HeroSwappedEvent => HeroSwappedEvent::from_structure(data).map(TrackerEventKind::HeroSwapped),
+            ReplayTrackerEventType::Unknown => None,
+        }
+    }
+}
+
+/// Builds a unit tag from its index and recycle count, the form used by the game events.
+pub fn unit_tag(index: u32, recycle: u32) -> u32 {
+    (index << 18).wrapping_add(recycle)
+}
+
+/// A unit being created.  Also used for `UnitInit`, which is sent when a unit starts being built.
+#[derive(Serialize, Clone, Debug)]
+pub struct UnitBornEvent {
+    pub unit_tag_index: u32,
+    pub unit_tag_recycle: u32,
+    pub unit_type_name: String,
+    pub control_player_id: u32,
+    pub upkeep_player_id: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl UnitBornEvent {
+    fn from_structure(data: &TrackerEventStructure) -> Option<UnitBornEvent> {
+        Some(UnitBornEvent {
+            unit_tag_index: get_u32(data, 0)?,
+            unit_tag_recycle: get_u32(data, 1)?,
+            unit_type_name: get_text(data, 2)?,
+            control_player_id: get_u32(data, 3)?,
+            upkeep_player_id: get_u32(data, 4)?,
+            x: get_u32(data, 5)?,
+            y: get_u32(data, 6)?,
+        })
+    }
+
+    pub fn unit_tag(&self) -> u32 {
+        unit_tag(self.unit_tag_index, self.unit_tag_recycle)
+    }
+}
+
+/// A unit dying, along with who killed it, if anyone.
+#[derive(Serialize, Clone, Debug)]
+pub struct UnitDiedEvent {
+    pub unit_tag_index: u32,
+    pub unit_tag_recycle: u32,
+    pub killer_player_id: Option<u32>,
+    pub x: u32,
+    pub y: u32,
+    pub killer_unit_tag_index: Option<u32>,
+    pub killer_unit_tag_recycle: Option<u32>,
+}
+
+impl UnitDiedEvent {
+    fn from_structure(data: &TrackerEventStructure) -> Option<UnitDiedEvent> {
+        Some(UnitDiedEvent {
+            unit_tag_index: get_u32(data, 0)?,
+            unit_tag_recycle: get_u32(data, 1)?,
+            killer_player_id: get_optional_u32(data, 2)?,
+            x: get_u32(data, 3)?,
+            y: get_u32(data, 4)?,
+            killer_unit_tag_index: get_optional_u32(data, 5)?,
+            killer_unit_tag_recycle: get_optional_u32(data, 6)?,
+        })
+    }
+
+    pub fn unit_tag(&self) -> u32 {
+        unit_tag(self.unit_tag_index, self.unit_tag_recycle)
+    }
+
+    pub fn killer_unit_tag(&self) -> Option<u32> {
+        match (self.killer_unit_tag_index, self.killer_unit_tag_recycle) {
+            (Some(index), Some(recycle)) => Some(unit_tag(index, recycle)),
+            _ => None
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct UnitOwnerChangeEvent {
+    pub unit_tag_index: u32,
+    pub unit_tag_recycle: u32,
+    pub control_player_id: u32,
+    pub upkeep_player_id: u32,
+}
+
+impl UnitOwnerChangeEvent {
+    fn from_structure(data: &TrackerEventStructure) -> Option<UnitOwnerChangeEvent> {
+        Some(UnitOwnerChangeEvent {
+            unit_tag_index: get_u32(data, 0)?,
+            unit_tag_recycle: get_u32(data, 1)?,
+            control_player_id: get_u32(data, 2)?,
+            upkeep_player_id: get_u32(data, 3)?,
+        })
+    }
+
+    pub fn unit_tag(&self) -> u32 {
+        unit_tag(self.unit_tag_index, self.unit_tag_recycle)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct UnitTypeChangeEvent {
+    pub unit_tag_index: u32,
+    pub unit_tag_recycle: u32,
+    pub unit_type_name: String,
+}
+
+impl UnitTypeChangeEvent {
+    fn from_structure(data: &TrackerEventStructure) -> Option<UnitTypeChangeEvent> {
+        Some(UnitTypeChangeEvent {
+            unit_tag_index: get_u32(data, 0)?,
+            unit_tag_recycle: get_u32(data, 1)?,
+            unit_type_name: get_text(data, 2)?,
+        })
+    }
+
+    pub fn unit_tag(&self) -> u32 {
+        unit_tag(self.unit_tag_index, self.unit_tag_recycle)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct UpgradeEvent {
+    pub player_id: u32,
+    pub upgrade_type_name: String,
+    pub count: i32,
+}
+
+impl UpgradeEvent {
+    fn from_structure(data: &TrackerEventStructure) -> Option<UpgradeEvent> {
+        Some(UpgradeEvent {
+            player_id: get_u32(data, 0)?,
+            upgrade_type_name: get_text(data, 1)?,
+            count: get_vint(data, 2)? as i32,
+        })
+    }
+}
+
+/// A unit finishing construction, sent after a matching `UnitInit`.
+#[derive(Serialize, Clone, Debug)]
+pub struct UnitDoneEvent {
+    pub unit_tag_index: u32,
+    pub unit_tag_recycle: u32,
+}
+
+impl UnitDoneEvent {
+    fn from_structure(data: &TrackerEventStructure) -> Option<UnitDoneEvent> {
+        Some(UnitDoneEvent {
+            unit_tag_index: get_u32(data, 0)?,
+            unit_tag_recycle: get_u32(data, 1)?,
+        })
+    }
+
+    pub fn unit_tag(&self) -> u32 {
+        unit_tag(self.unit_tag_index, self.unit_tag_recycle)
+    }
+}
+
+/// A batch of unit positions.  `items` holds triplets of unit index delta, x and y; `positions` decodes them.
+#[derive(Serialize, Clone, Debug)]
+pub struct UnitPositionsEvent {
+    pub first_unit_index: u32,
+    pub items: Vec<i64>,
+}
+
+/// A unit's position, as reported by a `UnitPositionsEvent`.
+///
+/// Only the unit tag index is given, which has to be matched up with whichever unit held that index at the
+/// time of the event to find the unit tag.
+#[derive(Serialize, Clone, Debug)]
+pub struct UnitIndexPosition {
+    pub unit_tag_index: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl UnitPositionsEvent {
+    fn from_structure(data: &TrackerEventStructure) -> Option<UnitPositionsEvent> {
+        let mut items = Vec::new();
+        for item in data.try_get_dict_entry(1)?.get_array() {
+            items.push(item.try_get_vint()?);
+        }
+
+        Some(UnitPositionsEvent {
+            first_unit_index: get_u32(data, 0)?,
+            items: items,
+        })
+    }
+
+    /// Decodes the positions in the batch.  Each unit index is stored as the difference from the one
+    /// before it, starting from `first_unit_index`.
+    ///
+    /// Unlike StarCraft II, which stores these divided by four, Heroes gives the coordinates in the same map units
+    /// as `UnitBornEvent` and `UnitDiedEvent`.  Positions that can't be map units are left out.
+    pub fn positions(&self) -> Vec<UnitIndexPosition> {
+        let mut unit_index = self.first_unit_index as i64;
+
+        self.items.chunks(3)
+            .filter(|item| item.len() == 3)
+            .filter_map(|item| {
+                unit_index = unit_index.wrapping_add(item[0]);
+                Some(UnitIndexPosition {
+                    unit_tag_index: unit_index as u32,
+                    x: to_map_coordinate(item[1])?,
+                    y: to_map_coordinate(item[2])?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Maps a tracker player id to the user id and lobby slot of that player.
+#[derive(Serialize, Clone, Debug)]
+pub struct PlayerSetupEvent {
+    pub player_id: u32,
+    pub player_type: u32,
+    pub user_id: Option<u32>,
+    pub slot_id: Option<u32>,
+}
+
+impl PlayerSetupEvent {
+    fn from_structure(data: &TrackerEventStructure) -> Option<PlayerSetupEvent> {
+        Some(PlayerSetupEvent {
+            player_id: get_u32(data, 0)?,
+            player_type: get_u32(data, 1)?,
+            user_id: get_optional_u32(data, 2)?,
+            slot_id: get_optional_u32(data, 3)?,
+        })
+    }
+}
+
+/// A named game statistic, with any number of string, integer and fixed-point values attached, each under a key.
+///
+/// Keys can repeat, such as `KillingPlayer` in `PlayerDeath` events, so each key maps to all of its values in
+/// the order they were given.  The `get_*` lookups return the first.
+#[derive(Serialize, Clone, Debug)]
+pub struct StatGameEvent {
+    pub name: String,
+    pub strings: HashMap<String, Vec<String>>,
+    pub ints: HashMap<String, Vec<i64>>,
+    pub fixeds: HashMap<String, Vec<f64>>,
+}
+
+impl StatGameEvent {
+    fn from_structure(data: &TrackerEventStructure) -> Option<StatGameEvent> {
+        Some(StatGameEvent {
+            name: get_text(data, 0)?,
+            strings: get_stat_entries(data, 1, |value| value.try_get_blob_text())?,
+            ints: get_stat_entries(data, 2, |value| value.try_get_vint())?,
+            // Fixed-point values have 12 fractional bits.
+            fixeds: get_stat_entries(data, 3, |value| value.try_get_vint().map(|value| value as f64 / 4096.0))?,
+        })
+    }
+
+    pub fn get_string(&self, key: &str) -> Option<&str> {
+        self.get_strings(key).first().map(|value| value.as_ref())
+    }
+
+    pub fn get_int(&self, key: &str) -> Option<i64> {
+        self.get_ints(key).first().cloned()
+    }
+
+    pub fn get_fixed(&self, key: &str) -> Option<f64> {
+        self.get_fixeds(key).first().cloned()
+    }
+
+    pub fn get_strings(&self, key: &str) -> &[String] {
+        self.strings.get(key).map(|values| values.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn get_ints(&self, key: &str) -> &[i64] {
+        self.ints.get(key).map(|values| values.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn get_fixeds(&self, key: &str) -> &[f64] {
+        self.fixeds.get(key).map(|values| values.as_slice()).unwrap_or(&[])
+    }
+}
+
+fn get_stat_entries<T, F>(data: &TrackerEventStructure, index: i32, get_value: F) -> Option<HashMap<String, Vec<T>>>
+    where F: Fn(&TrackerEventStructure) -> Option<T>
+{
+    let mut entries: HashMap<String, Vec<T>> = HashMap::new();
+    if let Some(items) = data.try_get_dict_entry(index)?.try_get_optional_data() {
+        for item in items.get_array() {
+            // The key is wrapped in a struct of its own.
+            let key = item.try_get_dict_entry(0)?;
+            let key = match key.try_get_dict_entry(0) {
+                Some(inner) => inner.try_get_blob_text()?,
+                None => key.try_get_blob_text()?,
+            };
+
+            let value = get_value(item.try_get_dict_entry(1)?)?;
+            entries.entry(key).or_insert_with(Vec::new).push(value);
+        }
+    }
+
+    Some(entries)
+}
+
+/// The end-of-game score screen: a list of named scores with a value for each player slot.
+#[derive(Serialize, Clone, Debug)]
+pub struct ScoreResultEvent {
+    pub instances: Vec<ScoreResultInstance>,
+}
+
+/// A single named score.  `values` is indexed by tracker player id minus one, and each slot holds the
+/// history of values for that player, which in practice is the single final value.
+#[derive(Serialize, Clone, Debug)]
+pub struct ScoreResultInstance {
+    pub name: String,
+    pub values: Vec<Vec<ScoreResultValue>>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ScoreResultValue {
+    pub value: i64,
+    pub time: Option<i64>,
+}
+
+impl ScoreResultEvent {
+    fn from_structure(data: &TrackerEventStructure) -> Option<ScoreResultEvent> {
+        let mut instances = Vec::new();
+        for instance in data.try_get_dict_entry(0)?.get_array() {
+            let mut values = Vec::new();
+            for player_values in instance.try_get_dict_entry(1)?.get_array() {
+                let mut player_history = Vec::new();
+                for value in player_values.get_array() {
+                    // The time is optional in older protocol versions.
+                    let time = value.try_get_dict_entry(1).and_then(|time| match time.try_get_optional_data() {
+                        Some(inner) => inner.try_get_vint(),
+                        None => time.try_get_vint(),
+                    });
+
+                    player_history.push(ScoreResultValue {
+                        value: get_vint(value, 0)?,
+                        time: time,
+                    });
+                }
+
+                values.push(player_history);
+            }
+
+            instances.push(ScoreResultInstance {
+                name: get_text(instance, 0)?,
+                values: values,
+            });
+        }
+
+        Some(ScoreResultEvent { instances: instances })
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct UnitRevivedEvent {
+    pub unit_tag_index: u32,
+    pub unit_tag_recycle: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl UnitRevivedEvent {
+    fn from_structure(data: &TrackerEventStructure) -> Option<UnitRevivedEvent> {
+        Some(UnitRevivedEvent {
+            unit_tag_index: get_u32(data, 0)?,
+            unit_tag_recycle: get_u32(data, 1)?,
+            x: get_u32(data, 2)?,
+            y: get_u32(data, 3)?,
+        })
+    }
+
+    pub fn unit_tag(&self) -> u32 {
+        unit_tag(self.unit_tag_index, self.unit_tag_recycle)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct HeroBannedEvent {
+    pub hero: String,
+    pub controlling_team: u32,
+}
+
+impl HeroBannedEvent {
+    fn from_structure(data: &TrackerEventStructure) -> Option<HeroBannedEvent> {
+        Some(HeroBannedEvent {
+            hero: get_text(data, 0)?,
+            controlling_team: get_u32(data, 1)?,
+        })
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct HeroPickedEvent {
+    pub hero: String,
+    pub controlling_player: u32,
+}
+
+impl HeroPickedEvent {
+    fn from_structure(data: &TrackerEventStructure) -> Option<HeroPickedEvent> {
+        Some(HeroPickedEvent {
+            hero: get_text(data, 0)?,
+            controlling_player: get_u32(data, 1)?,
+        })
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct HeroSwappedEvent {
+    pub hero: String,
+    pub new_controlling_player: u32,
+}
+
+impl HeroSwappedEvent {
+    fn from_structure(data: &TrackerEventStructure) -> Option<HeroSwappedEvent> {
+        Some(HeroSwappedEvent {
+            hero: get_text(data, 0)?,
+            new_controlling_player: get_u32(data, 1)?,
+        })
+    }
+}
+
+fn get_vint(data: &TrackerEventStructure, index: i32) -> Option<i64> {
+    data.try_get_dict_entry(index)?.try_get_vint()
+}
+
+fn get_u32(data: &TrackerEventStructure, index: i32) -> Option<u32> {
+    get_vint(data, index).map(|value| value as u32)
+}
+
+fn get_text(data: &TrackerEventStructure, index: i32) -> Option<String> {
+    data.try_get_dict_entry(index)?.try_get_blob_text()
+}
+
+// Returns `None` if the entry is malformed, and `Some(None)` if it's a valid but empty optional.
+fn get_optional_u32(data: &TrackerEventStructure, index: i32) -> Option<Option<u32>> {
+    let entry = data.try_get_dict_entry(index)?;
+    match entry.try_get_optional_data() {
+        Some(value) => value.try_get_vint().map(|value| Some(value as u32)),
+        None => Some(None),
+    }
+}
+
+fn to_map_coordinate(value: i64) -> Option<u32> {
+    if value >= 0 && value <= u32::max_value() as i64 {
+        Some(value as u32)
+    } else {
+        None
+    }
 }
 
 pub struct ReplayTrackerEvents {
 }
 
 impl ReplayTrackerEvents {
-    pub fn parse_replay_tracker_events(replay: &mut StormReplay, archive: &mut Archive) -> ReplayResult<()> {
-        match archive.open_file("replay.tracker.events") {
-            Ok(file) => {
-                let file_size = file.size();
-                let mut file_buf: Vec<u8> = vec![0; file_size as usize];
-
-                match file.read(archive, file_buf.as_mut()) {
-                    Ok(_) => {
-                        let mut reader = BinaryReader::new(&file_buf);
-                        let mut tracker_events: Vec<TrackerEvent> = Vec::new();
-                        let mut ticks_elapsed: u32 = 0;
-
-                        while !reader.eof() {
-                            let mut tracker_event: TrackerEvent = Default::default();
-
-                            // Per barrett777's notes, this is usually 03 ?? 09, where the middle byte has been at least two distinct values.
-                            reader.read_bytes(3)?;
-
-                            let ticks_delta = read_variable_int(&mut reader)?;
-                            ticks_elapsed += ticks_delta as u32;
-                            tracker_event.ticks_elapsed = ticks_elapsed;
-
-                            reader.read_bytes(1)?;
-
-                            let tracker_event_type_raw = read_variable_int(&mut reader)?;
-                            let tracker_event_type = ReplayTrackerEventType::from_u32(tracker_event_type_raw as u32)
-                                .ok_or(ReplayError::new(ReplayErrorKind::StructureError,
-                                    &format!("unknown tracker event type '{}'", tracker_event_type_raw)))?;
-                            tracker_event.event_type = tracker_event_type;
-
-                            let mut tracker_data = TrackerEventStructure::new(&mut reader)?;
-                            if tracker_event_type == ReplayTrackerEventType::StatGameEvent {
-                                let fixed_data = tracker_data.get_mut_dict_entry(3);
-                                if fixed_data.optional_data.is_some() {
-                                    let items = fixed_data.get_mut_optional_data().get_mut_array();
-                                    for item in items.iter_mut() {
-                                        let entry = item.get_mut_dict_entry(1);
-                                        entry.variable_int = Some(entry.get_vint() / 4096);
-                                    }
-                                }
-                            }
-
-                            tracker_event.data = tracker_data;
-
-                            tracker_events.push(tracker_event)
-                        }
-
-                        replay.tracker_events = tracker_events;
-
-                        Ok(())
+    pub fn parse_replay_tracker_events(replay: &mut StormReplay, archive: &mut ReplayArchive) -> ReplayResult<()> {
+        let file_buf = archive.read_file("replay.tracker.events")?;
+        let mut reader = BinaryReader::new(&file_buf);
+        // Decoded straight onto the replay, so an error partway through doesn't cost us the events before it.
+        replay.tracker_events = Vec::new();
+        let mut ticks_elapsed: u32 = 0;
+
+        while !reader.eof() {
+            // Per barrett777's notes, this is usually 03 ?? 09, where the middle byte has been at least two distinct values.
+            reader.read_bytes(3)?;
+
+            let ticks_delta = read_variable_int(&mut reader)?;
+            ticks_elapsed = ticks_elapsed.wrapping_add(ticks_delta as u32);
+
+            reader.read_bytes(1)?;
+
+            let tracker_event_type_raw = read_variable_int(&mut reader)?;
+            let tracker_event_type = ReplayTrackerEventType::from_u32(tracker_event_type_raw as u32)
+                .unwrap_or(ReplayTrackerEventType::Unknown);
+
+            // The encoding describes itself, so events of types we don't know can still be read, and skipped.  A
+            // data type we don't know leaves us with no way of finding the next event, though, so we stop there.
+            // Running out of data is an error like any other.
+            let tracker_data = match TrackerEventStructure::new(&mut reader) {
+                Ok(tracker_data) => tracker_data,
+                Err(e) => match e.kind {
+                    ReplayErrorKind::StructureError => {
+                        replay.warnings.push(format!("stopped reading tracker events at tick {}: {}", ticks_elapsed, e));
+                        break;
                     },
-                    Err(_) => Err(ReplayError::new(ReplayErrorKind::ArchiveError,  "failed to read tracker events file"))
-                }
-            },
-            Err(_) => Err(ReplayError::new(ReplayErrorKind::ArchiveError, "failed to open tracker events file"))
+                    _ => return Err(e),
+                },
+            };
+
+            let kind = match TrackerEventKind::from_structure(tracker_event_type, &tracker_data) {
+                Some(kind) => kind,
+                None => TrackerEventKind::Unknown { id: tracker_event_type_raw as u32, data: tracker_data },
+            };
+
+            replay.tracker_events.push(TrackerEvent {
+                event_type: tracker_event_type,
+                ticks_elapsed: ticks_elapsed,
+                kind: kind,
+            });
         }
+
+        Ok(())
     }
 }
 
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use storm_parser::replay::StormReplay;
+use storm_parser::tracker::{TrackerEventKind, UnitBornEvent};
+
+/// Every unit seen in the tracker events, and what happened to it over the course of the game.
+///
+/// Units are keyed by their unit tag, which stays the same for the unit's whole life, revivals included.
+#[derive(Serialize, Default, Debug)]
+pub struct UnitRegistry {
+    pub units: Vec<Unit>,
+    // Every position reported by the periodic unit position events, in the order they were reported.  These
+    // also show up in the position history of each unit.
+    pub position_samples: Vec<UnitPositionSample>,
+    #[serde(skip_serializing)]
+    positions_by_tag: HashMap<u32, usize>,
+    // The most recent unit to hold each unit tag index.
+    #[serde(skip_serializing)]
+    positions_by_index: HashMap<u32, usize>,
+}
+
+/// A single unit's lifecycle.  All of the histories are in the order they happened.
+#[derive(Serialize, Clone, Debug)]
+pub struct Unit {
+    pub unit_tag: u32,
+    pub unit_tag_index: u32,
+    pub unit_tag_recycle: u32,
+    pub born_ticks_elapsed: u32,
+    // Units that have to be built first are only done once they're finished.
+    pub done_ticks_elapsed: Option<u32>,
+    pub types: Vec<UnitTypeEntry>,
+    pub owners: Vec<UnitOwnerEntry>,
+    pub deaths: Vec<UnitDeathEntry>,
+    pub revivals: Vec<u32>,
+    pub positions: Vec<UnitPosition>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct UnitTypeEntry {
+    pub ticks_elapsed: u32,
+    pub unit_type_name: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct UnitOwnerEntry {
+    pub ticks_elapsed: u32,
+    // Tracker player ids.  Zero is neutral, and 11 and 12 are the two teams' AI.
+    pub control_player_id: u32,
+    pub upkeep_player_id: u32,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct UnitDeathEntry {
+    pub ticks_elapsed: u32,
+    pub killer_player_id: Option<u32>,
+    pub killer_unit_tag: Option<u32>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct UnitPosition {
+    pub ticks_elapsed: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct UnitPositionSample {
+    pub unit_tag: u32,
+    pub ticks_elapsed: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+/// A snapshot of a unit at a given point in the game.
+#[derive(Serialize, Clone, Debug)]
+pub struct UnitState {
+    pub unit_tag: u32,
+    pub unit_type_name: String,
+    pub control_player_id: u32,
+    pub upkeep_player_id: u32,
+    pub is_alive: bool,
+    // The last known position, which may lag behind where the unit really is.
+    pub position: Option<UnitPosition>,
+}
+
+impl UnitRegistry {
+    /// Looks up a unit by its unit tag.
+    pub fn get(&self, unit_tag: u32) -> Option<&Unit> {
+        self.positions_by_tag.get(&unit_tag).map(|&position| &self.units[position])
+    }
+
+    /// Looks up the state of the unit with the given unit tag at the given tick, if it existed by then.
+    pub fn get_at(&self, unit_tag: u32, ticks_elapsed: u32) -> Option<UnitState> {
+        self.get(unit_tag).and_then(|unit| unit.state_at(ticks_elapsed))
+    }
+
+    /// All of the units alive at the given tick.
+    pub fn alive_at(&self, ticks_elapsed: u32) -> Vec<&Unit> {
+        self.units.iter().filter(|unit| unit.is_alive_at(ticks_elapsed)).collect()
+    }
+
+    fn get_mut(&mut self, unit_tag: u32) -> Option<&mut Unit> {
+        match self.positions_by_tag.get(&unit_tag) {
+            Some(&position) => Some(&mut self.units[position]),
+            None => None,
+        }
+    }
+
+    /// The position samples of the unit with the given unit tag.
+    pub fn position_samples_for(&self, unit_tag: u32) -> Vec<&UnitPositionSample> {
+        self.position_samples.iter().filter(|sample| sample.unit_tag == unit_tag).collect()
+    }
+
+    fn add(&mut self, born: &UnitBornEvent, ticks_elapsed: u32, is_done: bool) {
+        let unit = Unit {
+            unit_tag: born.unit_tag(),
+            unit_tag_index: born.unit_tag_index,
+            unit_tag_recycle: born.unit_tag_recycle,
+            born_ticks_elapsed: ticks_elapsed,
+            done_ticks_elapsed: if is_done { Some(ticks_elapsed) } else { None },
+            types: vec![UnitTypeEntry { ticks_elapsed: ticks_elapsed, unit_type_name: born.unit_type_name.clone() }],
+            owners: vec![UnitOwnerEntry {
+                ticks_elapsed: ticks_elapsed,
+                control_player_id: born.control_player_id,
+                upkeep_player_id: born.upkeep_player_id,
+            }],
+            deaths: Vec::new(),
+            revivals: Vec::new(),
+            positions: vec![UnitPosition { ticks_elapsed: ticks_elapsed, x: born.x, y: born.y }],
+        };
+
+        self.positions_by_tag.insert(unit.unit_tag, self.units.len());
+        self.positions_by_index.insert(unit.unit_tag_index, self.units.len());
+        self.units.push(unit);
+    }
+
+    fn add_position_sample(&mut self, unit_tag_index: u32, ticks_elapsed: u32, x: u32, y: u32) {
+        let unit = match self.positions_by_index.get(&unit_tag_index) {
+            Some(&position) => &mut self.units[position],
+            None => return,
+        };
+
+        unit.positions.push(UnitPosition { ticks_elapsed: ticks_elapsed, x: x, y: y });
+        self.position_samples.push(UnitPositionSample {
+            unit_tag: unit.unit_tag,
+            ticks_elapsed: ticks_elapsed,
+            x: x,
+            y: y,
+        });
+    }
+}
+
+impl Unit {
+    /// The unit's type at the end of the game.
+    pub fn unit_type_name(&self) -> &str {
+        self.types.last().map(|entry| entry.unit_type_name.as_ref()).unwrap_or("")
+    }
+
+    /// The unit's type at the given tick, if it existed by then.
+    pub fn unit_type_at(&self, ticks_elapsed: u32) -> Option<&str> {
+        last_at(&self.types, ticks_elapsed, |entry| entry.ticks_elapsed).map(|entry| entry.unit_type_name.as_ref())
+    }
+
+    /// Who owned the unit at the given tick, if it existed by then.
+    pub fn owner_at(&self, ticks_elapsed: u32) -> Option<&UnitOwnerEntry> {
+        last_at(&self.owners, ticks_elapsed, |entry| entry.ticks_elapsed)
+    }
+
+    /// The last known position of the unit at the given tick, if it existed by then.
+    pub fn position_at(&self, ticks_elapsed: u32) -> Option<&UnitPosition> {
+        last_at(&self.positions, ticks_elapsed, |position| position.ticks_elapsed)
+    }
+
+    /// Whether the unit was alive at the given tick: born, and not dead or since revived.
+    pub fn is_alive_at(&self, ticks_elapsed: u32) -> bool {
+        if self.born_ticks_elapsed > ticks_elapsed {
+            return false
+        }
+
+        let last_death = last_at(&self.deaths, ticks_elapsed, |death| death.ticks_elapsed).map(|death| death.ticks_elapsed);
+        let last_revival = last_at(&self.revivals, ticks_elapsed, |&revival| revival).cloned();
+        match (last_death, last_revival) {
+            (Some(death), Some(revival)) => revival >= death,
+            (Some(_), None) => false,
+            _ => true,
+        }
+    }
+
+    /// A snapshot of the unit at the given tick, if it existed by then.
+    pub fn state_at(&self, ticks_elapsed: u32) -> Option<UnitState> {
+        let unit_type_name = self.unit_type_at(ticks_elapsed)?;
+        let owner = self.owner_at(ticks_elapsed)?;
+
+        Some(UnitState {
+            unit_tag: self.unit_tag,
+            unit_type_name: unit_type_name.to_string(),
+            control_player_id: owner.control_player_id,
+            upkeep_player_id: owner.upkeep_player_id,
+            is_alive: self.is_alive_at(ticks_elapsed),
+            position: self.position_at(ticks_elapsed).cloned(),
+        })
+    }
+}
+
+// Finds the last entry of a history at or before the given tick.
+fn last_at<T, F>(entries: &[T], ticks_elapsed: u32, get_ticks: F) -> Option<&T>
+    where F: Fn(&T) -> u32
+{
+    entries.iter().take_while(|entry| get_ticks(entry) <= ticks_elapsed).last()
+}
+
+pub struct ReplayUnits {
+}
+
+impl ReplayUnits {
+    /// Builds the unit registry from the unit events in the tracker stream.
+    pub fn parse_units(replay: &mut StormReplay) {
+        let mut registry: UnitRegistry = Default::default();
+
+        for event in replay.tracker_events.iter() {
+            let ticks_elapsed = event.ticks_elapsed;
+
+            match event.kind {
+                TrackerEventKind::UnitBorn(ref born) => registry.add(born, ticks_elapsed, true),
+                TrackerEventKind::UnitInit(ref init) => registry.add(init, ticks_elapsed, false),
+                TrackerEventKind::UnitDone(ref done) => {
+                    if let Some(unit) = registry.get_mut(done.unit_tag()) {
+                        unit.done_ticks_elapsed = Some(ticks_elapsed);
+                    }
+                },
+                TrackerEventKind::UnitDied(ref died) => {
+                    if let Some(unit) = registry.get_mut(died.unit_tag()) {
+                        unit.deaths.push(UnitDeathEntry {
+                            ticks_elapsed: ticks_elapsed,
+                            killer_player_id: died.killer_player_id,
+                            killer_unit_tag: died.killer_unit_tag(),
+                        });
+                        unit.positions.push(UnitPosition { ticks_elapsed: ticks_elapsed, x: died.x, y: died.y });
+                    }
+                },
+                TrackerEventKind::UnitRevived(ref revived) => {
+                    if let Some(unit) = registry.get_mut(revived.unit_tag()) {
+                        unit.revivals.push(ticks_elapsed);
+                        unit.positions.push(UnitPosition { ticks_elapsed: ticks_elapsed, x: revived.x, y: revived.y });
+                    }
+                },
+                TrackerEventKind::UnitPositions(ref positions) => {
+                    for position in positions.positions() {
+                        registry.add_position_sample(position.unit_tag_index, ticks_elapsed, position.x, position.y);
+                    }
+                },
+                TrackerEventKind::UnitOwnerChange(ref owner) => {
+                    if let Some(unit) = registry.get_mut(owner.unit_tag()) {
+                        unit.owners.push(UnitOwnerEntry {
+                            ticks_elapsed: ticks_elapsed,
+                            control_player_id: owner.control_player_id,
+                            upkeep_player_id: owner.upkeep_player_id,
+                        });
+                    }
+                },
+                TrackerEventKind::UnitTypeChange(ref type_change) => {
+                    if let Some(unit) = registry.get_mut(type_change.unit_tag()) {
+                        unit.types.push(UnitTypeEntry {
+                            ticks_elapsed: ticks_elapsed,
+                            unit_type_name: type_change.unit_type_name.clone(),
+                        });
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        replay.units = registry;
+    }
+}
@@ -0,0 +1,65 @@
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::StormParser;
+
+#[test]
+fn battle_tags_are_matched_to_their_players() {
+    let replay = common::parse_test_replay();
+
+    let mut tags = Vec::new();
+    for player in replay.players.iter() {
+        let tag = player.battle_tag.clone().expect("every player has a BattleTag");
+        assert!(tag.starts_with(&format!("{}#", player.name)));
+        assert!(!tags.contains(&tag));
+        tags.push(tag);
+
+        let level = player.account_level.expect("every player has an account level");
+        assert!(level > 0 && level <= 10_000);
+    }
+
+    assert_eq!(replay.players[0].battle_tag, Some("Ninlarr#1744".to_string()));
+    assert_eq!(replay.players[0].account_level, Some(1306));
+}
+
+#[test]
+fn implausible_account_levels_are_dropped() {
+    let mut archive = common::PatchedArchive::new()
+        .patch("replay.server.battlelobby", |mut data| {
+            let tag = b"Ninlarr#1744";
+            let end = data.windows(tag.len()).position(|window| window == &tag[..]).unwrap() + tag.len();
+            for b in data[end..end + 4].iter_mut() {
+                *b = 0xFF;
+            }
+            data
+        });
+    let replay = StormParser::parse_archive(&mut archive).unwrap();
+
+    assert_eq!(replay.players[0].battle_tag, Some("Ninlarr#1744".to_string()));
+    assert_eq!(replay.players[0].account_level, None);
+    assert_eq!(replay.players[1].account_level, Some(581));
+}
+
+#[test]
+fn battle_tags_are_found_in_any_order() {
+    // Swap the first and third players' tags, along with their account levels, so the lobby lists them out of
+    // the order they have in the details.
+    let mut archive = common::PatchedArchive::new()
+        .patch("replay.server.battlelobby", |mut data| {
+            let find = |data: &Vec<u8>, tag: &[u8]| data.windows(tag.len()).position(|window| window == tag).unwrap();
+            let first = find(&data, b"Ninlarr#1744");
+            let third = find(&data, b"Kadajto#1386");
+            for i in 0..16 {
+                data.swap(first + i, third + i);
+            }
+            data
+        });
+    let replay = StormParser::parse_archive(&mut archive).unwrap();
+
+    let expected = [("Ninlarr#1744", 1306), ("GhostKnight#1319", 581), ("Kadajto#1386", 179), ("ZeekeraTron#1789", 233)];
+    for (player, &(tag, level)) in replay.players.iter().zip(expected.iter()) {
+        assert_eq!(player.battle_tag, Some(tag.to_string()));
+        assert_eq!(player.account_level, Some(level));
+    }
+}
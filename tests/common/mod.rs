@@ -0,0 +1,66 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use storm_parser::{ReplayArchive, ReplayResult, StormParser, StormReplay, StreamArchive};
+
+/// The replay bundled with the repository: a game of Infernal Shrines from November 2017.
+pub const TEST_REPLAY: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/test-replays/infernal-shrines-20171102.StormReplay");
+
+/// The files that make up a replay, as stored in the archive.
+pub const REPLAY_FILES: [&'static str; 7] = [
+    "replay.details",
+    "replay.initData",
+    "replay.attributes.events",
+    "replay.server.battlelobby",
+    "replay.game.events",
+    "replay.message.events",
+    "replay.tracker.events",
+];
+
+pub fn read_test_replay() -> Vec<u8> {
+    use std::fs::File;
+    use std::io::Read;
+
+    let mut buf = Vec::new();
+    File::open(TEST_REPLAY).and_then(|mut file| file.read_to_end(&mut buf)).expect("failed to read test replay");
+    buf
+}
+
+pub fn parse_test_replay() -> StormReplay {
+    StormParser::parse_replay(TEST_REPLAY).expect("failed to parse test replay")
+}
+
+/// The test replay, with some of its files swapped out for other contents.
+pub struct PatchedArchive {
+    data: Vec<u8>,
+    patches: HashMap<String, Vec<u8>>,
+}
+
+impl PatchedArchive {
+    pub fn new() -> PatchedArchive {
+        PatchedArchive { data: read_test_replay(), patches: HashMap::new() }
+    }
+
+    /// Replaces the named file with the result of `patch`, which is handed its original contents.
+    pub fn patch<F>(mut self, name: &str, patch: F) -> PatchedArchive
+        where F: FnOnce(Vec<u8>) -> Vec<u8>
+    {
+        let original = StreamArchive::from_bytes(&self.data).unwrap().read_file(name).unwrap();
+        self.patches.insert(name.to_string(), patch(original));
+        self
+    }
+}
+
+impl ReplayArchive for PatchedArchive {
+    fn read_user_data(&mut self) -> ReplayResult<Option<Vec<u8>>> {
+        StreamArchive::from_bytes(&self.data)?.read_user_data()
+    }
+
+    fn read_file(&mut self, name: &str) -> ReplayResult<Vec<u8>> {
+        match self.patches.get(name) {
+            Some(patched) => Ok(patched.clone()),
+            None => StreamArchive::from_bytes(&self.data)?.read_file(name),
+        }
+    }
+}
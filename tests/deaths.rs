@@ -0,0 +1,26 @@
+extern crate storm_parser;
+
+mod common;
+
+#[test]
+fn hero_deaths_are_credited_to_the_other_team() {
+    let replay = common::parse_test_replay();
+    assert_eq!(replay.deaths.len(), 37);
+
+    for death in replay.deaths.iter() {
+        let team = replay.players[death.victim].team;
+
+        assert!(!death.killers.is_empty());
+        for killer in death.killers.iter() {
+            assert!(replay.players[*killer].team != team);
+        }
+
+        if let Some(respawn) = death.respawn_ticks_elapsed {
+            assert!(respawn > death.ticks_elapsed);
+        }
+    }
+
+    for pair in replay.deaths.windows(2) {
+        assert!(pair[0].ticks_elapsed <= pair[1].ticks_elapsed);
+    }
+}
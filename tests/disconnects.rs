@@ -0,0 +1,27 @@
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::LeaveReason;
+
+#[test]
+fn everyone_quits_once_the_game_is_over() {
+    let replay = common::parse_test_replay();
+    assert_eq!(replay.disconnects.len(), 5);
+
+    let core_destroyed = replay.structures.destroyed.last().unwrap().ticks_elapsed;
+    for disconnect in replay.disconnects.iter() {
+        assert_eq!(disconnect.reason, LeaveReason::Quit);
+        assert!(disconnect.after_game_end);
+        assert!(disconnect.ticks_elapsed >= core_destroyed);
+        assert_eq!(disconnect.rejoined_ticks_elapsed, None);
+    }
+}
+
+#[test]
+fn leave_reasons_are_named() {
+    assert_eq!(LeaveReason::from_u32(0), LeaveReason::Quit);
+    assert_eq!(LeaveReason::from_u32(1), LeaveReason::Other(1));
+    assert_eq!(LeaveReason::from_u32(2), LeaveReason::Other(2));
+    assert_eq!(LeaveReason::from_u32(9), LeaveReason::Other(9));
+}
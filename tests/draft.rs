@@ -0,0 +1,121 @@
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::{DraftStepKind, GameMode, ParseOptions, StormParser};
+
+#[test]
+fn quick_match_has_no_draft() {
+    let replay = common::parse_test_replay();
+    assert_eq!(replay.game_mode, GameMode::QuickMatch);
+
+    assert!(replay.draft.steps.is_empty());
+    for team in 0..2 {
+        assert!(replay.draft.bans(team).is_empty());
+        assert!(replay.draft.picks(team).is_empty());
+    }
+}
+
+#[test]
+fn attribute_bans_are_empty_without_tracker_events() {
+    let options = ParseOptions::new().tracker_events(false);
+    let replay = StormParser::parse_replay_with_options(common::TEST_REPLAY, options).unwrap();
+
+    assert!(replay.draft.steps.is_empty());
+}
+
+// A tracker event at the end of the stream: the hero's name, and then either the team or the player.
+fn draft_event(event_type: u8, hero: &str, id: u8) -> Vec<u8> {
+    let mut event = vec![0x03, 0x00, 0x09, 0x00, 0x09, event_type << 1, 0x05, 0x04, 0x00, 0x02, (hero.len() as u8) << 1];
+    event.extend_from_slice(hero.as_bytes());
+    event.extend_from_slice(&[0x02, 0x09, id << 1]);
+    event
+}
+
+// A game attribute holding a four-letter hero value, which the attributes store back to front.
+fn ban_attribute(attribute_type: u32, hero: &[u8; 4]) -> Vec<u8> {
+    let mut attribute = vec![0xE7, 0x03, 0x00, 0x00];
+    attribute.extend_from_slice(&[attribute_type as u8, (attribute_type >> 8) as u8, 0x00, 0x00]);
+    attribute.push(0x00);
+    attribute.extend(hero.iter().rev());
+    attribute
+}
+
+fn add_ban_attributes(mut data: Vec<u8>) -> Vec<u8> {
+    // Out of order, to make sure they're sorted.
+    let bans: [(u32, &[u8; 4]); 4] = [(4030, b"Uthr"), (4023, b"Abat"), (4028, b"Tass"), (4025, b"Gene")];
+
+    let count = data[5] as u32 | (data[6] as u32) << 8 | (data[7] as u32) << 16 | (data[8] as u32) << 24;
+    let count = count + bans.len() as u32;
+    for (i, b) in data[5..9].iter_mut().enumerate() {
+        *b = (count >> (i * 8)) as u8;
+    }
+
+    for &(attribute_type, hero) in bans.iter() {
+        data.extend(ban_attribute(attribute_type, hero));
+    }
+    data
+}
+
+#[test]
+fn draft_follows_the_tracker_events() {
+    let plain = common::parse_test_replay();
+    let tracker_id = |position: usize| {
+        plain.player_identities.iter().find(|identity| identity.position == position).unwrap().tracker_player_id as u8
+    };
+    let (first, sixth) = (tracker_id(0), tracker_id(5));
+
+    let mut events = Vec::new();
+    events.extend(draft_event(13, "Abathur", 1));
+    events.extend(draft_event(13, "Tassadar", 2));
+    events.extend(draft_event(14, "Valla", first));
+    events.extend(draft_event(14, "Muradin", sixth));
+    events.extend(draft_event(15, "Valla", sixth));
+    events.extend(draft_event(13, "Nobody", 0));
+
+    let mut archive = common::PatchedArchive::new()
+        .patch("replay.tracker.events", move |mut data| { data.extend_from_slice(&events); data })
+        .patch("replay.attributes.events", add_ban_attributes);
+    let replay = StormParser::parse_archive(&mut archive).unwrap();
+
+    let (first_team, sixth_team) = (replay.players[0].team, replay.players[5].team);
+    let expected = [
+        (DraftStepKind::Ban, Some(0), None, "Abathur"),
+        (DraftStepKind::Ban, Some(1), None, "Tassadar"),
+        (DraftStepKind::Pick, Some(first_team), Some(0), "Valla"),
+        (DraftStepKind::Pick, Some(sixth_team), Some(5), "Muradin"),
+        (DraftStepKind::Swap, Some(sixth_team), Some(5), "Valla"),
+        (DraftStepKind::Ban, None, None, "Nobody"),
+    ];
+
+    // The tracker events replace the bans from the attributes entirely.
+    assert_eq!(replay.draft.steps.len(), expected.len());
+    for (order, (step, &(kind, team, player, hero))) in replay.draft.steps.iter().zip(expected.iter()).enumerate() {
+        assert_eq!(step.order, order as u32);
+        assert_eq!(step.kind, kind);
+        assert_eq!(step.team, team);
+        assert_eq!(step.player, player);
+        assert_eq!(step.hero, hero);
+    }
+
+    assert_eq!(replay.draft.bans(0).iter().map(|step| step.hero.as_ref()).collect::<Vec<&str>>(), vec!["Abathur"]);
+    assert_eq!(replay.draft.picks(first_team).iter().map(|step| step.player).collect::<Vec<_>>(), vec![Some(0)]);
+}
+
+#[test]
+fn bans_fall_back_to_the_attributes() {
+    let mut archive = common::PatchedArchive::new()
+        .patch("replay.attributes.events", add_ban_attributes);
+    let replay = StormParser::parse_archive(&mut archive).unwrap();
+
+    // Each team's bans keep their order within the team, and the teams follow one another.
+    let expected = [(0, 0, "Abat"), (1, 0, "Gene"), (0, 1, "Tass"), (1, 1, "Uthr")];
+    assert_eq!(replay.draft.steps.len(), expected.len());
+    for (step, &(order, team, hero)) in replay.draft.steps.iter().zip(expected.iter()) {
+        assert_eq!(step.order, order);
+        assert_eq!(step.kind, DraftStepKind::Ban);
+        assert_eq!(step.team, Some(team));
+        assert_eq!(step.player, None);
+        assert_eq!(step.hero, hero);
+    }
+}
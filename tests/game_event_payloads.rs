@@ -0,0 +1,49 @@
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::{GameEventKind, StormReplay, TargetPoint};
+
+fn on_map(replay: &StormReplay, point: &TargetPoint) -> bool {
+    point.x >= 0.0 && point.x <= replay.map_size.x as f64 && point.y >= 0.0 && point.y <= replay.map_size.y as f64
+}
+
+#[test]
+fn mouse_moves_are_decoded() {
+    let replay = common::parse_test_replay();
+
+    let mut moves = 0;
+    for event in replay.game_events.iter() {
+        if let GameEventKind::TriggerMouseMoved(ref moved) = event.kind {
+            assert!(moved.ui_x < 2048 && moved.ui_y < 2048);
+            assert!(on_map(&replay, &moved.world));
+            moves += 1;
+        }
+    }
+    assert_eq!(moves, 131218);
+}
+
+#[test]
+fn command_updates_are_decoded() {
+    let replay = common::parse_test_replay();
+
+    let mut sequenced = 0;
+    for event in replay.game_events.iter() {
+        let sequence = match event.kind {
+            GameEventKind::CmdUpdateTargetPoint(ref update) => {
+                assert!(on_map(&replay, &update.target));
+                update.sequence
+            },
+            GameEventKind::CmdUpdateTargetUnit(ref update) => {
+                assert!(on_map(&replay, &update.target.snapshot_point));
+                update.sequence
+            },
+            _ => continue,
+        };
+
+        if sequence.is_some() {
+            sequenced += 1;
+        }
+    }
+    assert!(sequenced > 0);
+}
@@ -0,0 +1,35 @@
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::GameEventKind;
+
+#[test]
+fn game_events_are_typed() {
+    let replay = common::parse_test_replay();
+    assert_eq!(replay.game_events.len(), 213898);
+    assert!(replay.warnings.is_empty());
+
+    for event in replay.game_events.iter() {
+        if let GameEventKind::Unknown = event.kind {
+            panic!("game event {:?} at tick {} wasn't decoded", event.event_type, event.ticks_elapsed);
+        }
+    }
+
+    let commands = replay.game_events.iter()
+        .filter(|event| match event.kind { GameEventKind::Cmd(_) => true, _ => false })
+        .count();
+    assert!(commands > 0);
+}
+
+#[test]
+fn every_player_sends_their_options() {
+    let replay = common::parse_test_replay();
+
+    let mut players: Vec<usize> = replay.game_events.iter()
+        .filter(|event| match event.kind { GameEventKind::UserOptions(_) => true, _ => false })
+        .filter_map(|event| replay.get_game_event_player(event))
+        .collect();
+    players.sort();
+    assert_eq!(players, (0..replay.players.len()).collect::<Vec<_>>());
+}
@@ -0,0 +1,50 @@
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::{ParseOptions, StormParser};
+
+#[test]
+fn every_numbering_resolves_to_the_same_player() {
+    let replay = common::parse_test_replay();
+    assert_eq!(replay.player_identities.len(), replay.players.len());
+
+    for (position, identity) in replay.player_identities.iter().enumerate() {
+        assert_eq!(identity.position, position);
+        assert_eq!(identity.slot_id, replay.players[position].index);
+
+        let attribute_player_id = identity.attribute_player_id.expect("the attributes cover every player");
+        let user_id = identity.user_id.expect("every player has a user id");
+        let hero_unit_tag = identity.hero_unit_tag.expect("every player has a hero");
+
+        assert_eq!(replay.get_player_position_by_slot_id(identity.slot_id), Some(position));
+        assert_eq!(replay.get_player_position_by_attribute_id(attribute_player_id), Some(position));
+        assert_eq!(replay.get_player_position_by_user_id(user_id), Some(position));
+        assert_eq!(replay.get_player_position_by_tracker_id(identity.tracker_player_id), Some(position));
+        assert_eq!(replay.get_player_position_by_hero_unit_tag(hero_unit_tag), Some(position));
+    }
+}
+
+#[test]
+fn user_ids_come_from_the_init_data_without_tracker_events() {
+    let with_tracker = common::parse_test_replay();
+
+    let options = ParseOptions::new().tracker_events(false);
+    let replay = StormParser::parse_replay_with_options(common::TEST_REPLAY, options).unwrap();
+
+    for (identity, expected) in replay.player_identities.iter().zip(with_tracker.player_identities.iter()) {
+        assert_eq!(identity.user_id, expected.user_id);
+        assert_eq!(identity.attribute_player_id, expected.attribute_player_id);
+    }
+}
+
+#[test]
+fn ids_are_left_unknown_without_their_sections() {
+    let options = ParseOptions::new().init(false).attributes(false).tracker_events(false);
+    let replay = StormParser::parse_replay_with_options(common::TEST_REPLAY, options).unwrap();
+
+    for identity in replay.player_identities.iter() {
+        assert_eq!(identity.user_id, None);
+        assert_eq!(identity.attribute_player_id, None);
+    }
+}
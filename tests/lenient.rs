@@ -0,0 +1,65 @@
+extern crate serde_json;
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::{ParseOptions, ReplaySection, StormParser};
+
+fn truncated(name: &str) -> common::PatchedArchive {
+    common::PatchedArchive::new()
+        .patch(name, |mut data| { let len = data.len() / 2 + 1; data.truncate(len); data })
+}
+
+#[test]
+fn an_intact_replay_is_complete() {
+    let mut archive = common::PatchedArchive::new();
+    let partial = StormParser::parse_archive_lenient(&mut archive, ParseOptions::new()).unwrap();
+
+    assert!(partial.is_complete());
+    assert!(partial.errors().is_empty());
+    assert_eq!(partial.replay.game_events.len(), 213898);
+}
+
+#[test]
+fn truncated_event_streams_keep_what_was_decoded() {
+    let streams = [
+        ("replay.game.events", ReplaySection::GameEvents),
+        ("replay.message.events", ReplaySection::MessageEvents),
+        ("replay.tracker.events", ReplaySection::TrackerEvents),
+    ];
+
+    for &(name, section) in streams.iter() {
+        assert!(StormParser::parse_archive(&mut truncated(name)).is_err());
+
+        let partial = StormParser::parse_archive_lenient(&mut truncated(name), ParseOptions::new()).unwrap();
+        let status = partial.get_section(section).unwrap();
+        assert!(!status.complete);
+        assert!(status.error.is_some());
+        assert_eq!(partial.errors().len(), 1);
+
+        let decoded = match section {
+            ReplaySection::GameEvents => partial.replay.game_events.len(),
+            ReplaySection::MessageEvents => partial.replay.message_events.len(),
+            _ => partial.replay.tracker_events.len(),
+        };
+        assert!(decoded > 0, "nothing kept from {}", name);
+
+        // Everything else is still there.
+        assert_eq!(partial.replay.players.len(), 10);
+        assert!(partial.get_section(ReplaySection::Details).unwrap().complete);
+    }
+}
+
+#[test]
+fn partial_replays_serialize_with_their_errors() {
+    let partial = StormParser::parse_archive_lenient(&mut truncated("replay.tracker.events"), ParseOptions::new()).unwrap();
+
+    let json = serde_json::to_value(&partial).unwrap();
+    let sections = json["sections"].as_array().unwrap();
+    let tracker = sections.iter().find(|status| status["section"] == "TrackerEvents").unwrap();
+    assert_eq!(tracker["complete"], false);
+    assert!(tracker["error"]["msg"].is_string());
+    assert!(json["replay"]["tracker_events"].as_array().map_or(false, |events| !events.is_empty()));
+
+    assert!(!format!("{:?}", partial).is_empty());
+}
@@ -0,0 +1,38 @@
+extern crate storm_parser;
+
+mod common;
+
+#[test]
+fn teams_level_up_in_order() {
+    let replay = common::parse_test_replay();
+    assert_eq!(replay.teams.len(), 2);
+
+    for (index, team) in replay.teams.iter().enumerate() {
+        assert_eq!(team.team, index as u32);
+        assert!(!team.levels.is_empty());
+
+        for pair in team.levels.windows(2) {
+            assert_eq!(pair[1].level, pair[0].level + 1);
+            assert!(pair[0].ticks_elapsed <= pair[1].ticks_elapsed);
+        }
+
+        // The team's final level is the one on every member's score screen.
+        let final_level = team.levels.last().unwrap().level;
+        for player in replay.players.iter().filter(|player| player.team == team.team) {
+            assert_eq!(player.score.as_ref().unwrap().level, final_level as i64);
+        }
+    }
+}
+
+#[test]
+fn team_experience_only_grows() {
+    let replay = common::parse_test_replay();
+
+    for team in replay.teams.iter() {
+        assert!(!team.xp.is_empty());
+        for pair in team.xp.windows(2) {
+            assert!(pair[0].ticks_elapsed < pair[1].ticks_elapsed);
+            assert!(pair[0].total_xp() <= pair[1].total_xp());
+        }
+    }
+}
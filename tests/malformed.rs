@@ -0,0 +1,57 @@
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::StormParser;
+
+const SECTIONS: [&'static str; 6] = [
+    "replay.details",
+    "replay.initData",
+    "replay.attributes.events",
+    "replay.game.events",
+    "replay.message.events",
+    "replay.tracker.events",
+];
+
+#[test]
+fn a_huge_attribute_count_is_an_error() {
+    let mut archive = common::PatchedArchive::new()
+        .patch("replay.attributes.events", |mut data| {
+            for b in data[5..9].iter_mut() {
+                *b = 0xFF;
+            }
+            data
+        });
+
+    assert!(StormParser::parse_archive(&mut archive).is_err());
+}
+
+#[test]
+fn truncated_sections_do_not_panic() {
+    for name in SECTIONS.iter() {
+        for &fraction in [0, 1, 2, 3].iter() {
+            let mut archive = common::PatchedArchive::new()
+                .patch(name, |mut data| { let len = data.len() * fraction / 4; data.truncate(len); data });
+
+            // Whether there's enough left to make a replay of doesn't matter, so long as we get an answer.
+            let _ = StormParser::parse_archive(&mut archive);
+        }
+    }
+}
+
+#[test]
+fn scrambled_sections_do_not_panic() {
+    for name in SECTIONS.iter() {
+        let mut archive = common::PatchedArchive::new()
+            .patch(name, |mut data| {
+                for (i, b) in data.iter_mut().enumerate() {
+                    if i % 7 == 3 {
+                        *b = b.wrapping_mul(31).wrapping_add(i as u8);
+                    }
+                }
+                data
+            });
+
+        let _ = StormParser::parse_archive(&mut archive);
+    }
+}
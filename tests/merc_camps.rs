@@ -0,0 +1,40 @@
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::MercCampKind;
+
+#[test]
+fn camp_captures_add_up_per_team() {
+    let replay = common::parse_test_replay();
+    let camps = &replay.merc_camps;
+    assert_eq!(camps.captures.len(), 9);
+    assert_eq!(camps.team_totals, vec![0, 9]);
+
+    for team in 0..2 {
+        let captured = camps.captures.iter().filter(|capture| capture.team == Some(team)).count();
+        assert_eq!(camps.team_totals[team as usize], captured as u32);
+    }
+
+    assert_eq!(camps.captured_by(1, MercCampKind::Siege).len(), 7);
+    assert_eq!(camps.captured_by(1, MercCampKind::Bruiser).len(), 2);
+    assert!(camps.captured_by(0, MercCampKind::Siege).is_empty());
+}
+
+#[test]
+fn camps_are_located_on_the_map() {
+    let replay = common::parse_test_replay();
+
+    for capture in replay.merc_camps.captures.iter() {
+        assert!(capture.camp_type.is_some());
+
+        let x = capture.x.unwrap();
+        let y = capture.y.unwrap();
+        assert!(x > 0.0 && x < replay.map_size.x as f64);
+        assert!(y > 0.0 && y < replay.map_size.y as f64);
+    }
+
+    for pair in replay.merc_camps.captures.windows(2) {
+        assert!(pair[0].ticks_elapsed <= pair[1].ticks_elapsed);
+    }
+}
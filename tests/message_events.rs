@@ -0,0 +1,41 @@
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::{MessageEventKind, StormParser};
+
+#[test]
+fn message_events_are_decoded() {
+    let replay = common::parse_test_replay();
+
+    let loading = replay.message_events.iter()
+        .filter(|event| match event.kind { MessageEventKind::LoadingProgress(_) => true, _ => false })
+        .count();
+    assert!(loading > 0);
+
+    // Every message comes from a player in the game.
+    for event in replay.message_events.iter() {
+        if event.player.is_some() {
+            assert!(replay.get_message_event_player(event).is_some());
+        }
+    }
+
+    let chat: Vec<&str> = replay.message_events.iter()
+        .filter_map(|event| match event.kind { MessageEventKind::Chat(ref chat) => Some(chat.message.as_ref()), _ => None })
+        .collect();
+    assert!(chat.iter().all(|message| !message.is_empty()));
+}
+
+#[test]
+fn unknown_message_events_stop_the_stream() {
+    let expected = common::parse_test_replay().message_events.len();
+
+    // A single event of type 15, which doesn't exist, at the very end of the stream.
+    let mut archive = common::PatchedArchive::new()
+        .patch("replay.message.events", |mut data| { data.extend_from_slice(&[0x00, 0xE0, 0x01]); data });
+    let replay = StormParser::parse_archive(&mut archive).unwrap();
+
+    assert_eq!(replay.message_events.len(), expected);
+    assert_eq!(replay.warnings.len(), 1);
+    assert!(replay.warnings[0].contains("unknown message event type '15'"));
+}
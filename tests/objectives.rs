@@ -0,0 +1,48 @@
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::{MapObjectives, StormParser};
+
+#[test]
+fn shrine_rounds_are_extracted() {
+    let replay = common::parse_test_replay();
+
+    let rounds = match replay.objectives {
+        Some(MapObjectives::InfernalShrines(ref objectives)) => &objectives.rounds,
+        None => panic!("no objectives for Infernal Shrines"),
+    };
+    assert_eq!(rounds.len(), 4);
+
+    for (index, round) in rounds.iter().enumerate() {
+        assert_eq!(round.round, index as u32 + 1);
+        assert!(round.captured_ticks_elapsed.is_some());
+        assert!(round.winning_team.is_some());
+        assert!(round.winning_score > round.losing_score);
+        assert!(round.x.is_some() && round.y.is_some());
+    }
+
+    let killed = rounds.iter()
+        .filter(|round| round.punisher.as_ref().map_or(false, |punisher| punisher.killed_ticks_elapsed.is_some()))
+        .count();
+    assert_eq!(killed, 3);
+}
+
+#[test]
+fn the_map_is_recognized_whatever_its_name() {
+    // Another name of the same length, as the details would have it in another locale.
+    let mut archive = common::PatchedArchive::new()
+        .patch("replay.details", |mut data| {
+            let name = b"Infernal Shrines";
+            let start = data.windows(name.len()).position(|window| window == &name[..]).unwrap();
+            data[start..start + name.len()].copy_from_slice(b"Infernale Altare");
+            data
+        });
+    let replay = StormParser::parse_archive(&mut archive).unwrap();
+
+    assert_eq!(replay.map, "Infernale Altare");
+    match replay.objectives {
+        Some(MapObjectives::InfernalShrines(ref objectives)) => assert_eq!(objectives.rounds.len(), 4),
+        None => panic!("no objectives for Infernal Shrines"),
+    }
+}
@@ -0,0 +1,38 @@
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::{ParseOptions, StormParser};
+
+#[test]
+fn header_only_skips_every_section() {
+    let replay = StormParser::parse_replay_with_options(common::TEST_REPLAY, ParseOptions::header_only()).unwrap();
+
+    assert!(replay.replay_build > 0);
+    assert!(replay.players.is_empty());
+    assert!(replay.game_events.is_empty());
+    assert!(replay.message_events.is_empty());
+    assert!(replay.tracker_events.is_empty());
+}
+
+#[test]
+fn tracker_events_bring_in_the_players() {
+    let options = ParseOptions::header_only().tracker_events(true);
+    let replay = StormParser::parse_replay_with_options(common::TEST_REPLAY, options).unwrap();
+
+    assert_eq!(replay.players.len(), 10);
+    assert!(!replay.tracker_events.is_empty());
+    assert!(replay.game_events.is_empty());
+    assert!(replay.players.iter().all(|player| player.score.is_some()));
+}
+
+#[test]
+fn skipping_game_events_keeps_the_rest() {
+    let options = ParseOptions::new().game_events(false);
+    let replay = StormParser::parse_replay_with_options(common::TEST_REPLAY, options).unwrap();
+
+    assert!(replay.game_events.is_empty());
+    assert!(!replay.message_events.is_empty());
+    assert!(!replay.tracker_events.is_empty());
+    assert_eq!(replay.map, "Infernal Shrines");
+}
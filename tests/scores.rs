@@ -0,0 +1,34 @@
+extern crate storm_parser;
+
+mod common;
+
+#[test]
+fn every_player_has_a_score() {
+    let replay = common::parse_test_replay();
+
+    for (position, player) in replay.players.iter().enumerate() {
+        let score = player.score.as_ref().expect("every player has a score");
+
+        assert!(score.level > 0);
+        assert!(score.hero_damage > 0);
+        assert!(score.experience_contribution > 0);
+        assert!(score.takedowns >= score.solo_kills);
+
+        let deaths = replay.deaths.iter().filter(|death| death.victim == position).count();
+        assert_eq!(score.deaths, deaths as i64);
+    }
+}
+
+#[test]
+fn teammates_share_a_level() {
+    let replay = common::parse_test_replay();
+
+    for team in 0..2 {
+        let levels: Vec<i64> = replay.players.iter()
+            .filter(|player| player.team == team)
+            .map(|player| player.score.as_ref().unwrap().level)
+            .collect();
+        assert_eq!(levels.len(), 5);
+        assert!(levels.iter().all(|level| *level == levels[0]));
+    }
+}
@@ -0,0 +1,27 @@
+extern crate storm_parser;
+
+mod common;
+
+#[test]
+fn regen_globes_are_counted_per_player() {
+    let replay = common::parse_test_replay();
+
+    let total: usize = replay.players.iter().map(|player| player.regen_globes().len()).sum();
+    assert_eq!(total, 207);
+
+    for player in replay.players.iter() {
+        assert!(player.regen_globes().windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(player.get_stat_counter("NotAStatEvent").is_empty());
+    }
+}
+
+#[test]
+fn only_player_counters_are_collected() {
+    let replay = common::parse_test_replay();
+
+    for player in replay.players.iter() {
+        // These carry more than the player id, so they aren't counters.
+        assert!(player.get_stat_counter("PlayerDeath").is_empty());
+        assert!(player.get_stat_counter("LevelUp").is_empty());
+    }
+}
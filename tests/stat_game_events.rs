@@ -0,0 +1,53 @@
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::{StatGameEvent, StormReplay, TrackerEventKind};
+
+fn stat_events<'a>(replay: &'a StormReplay, name: &str) -> Vec<&'a StatGameEvent> {
+    replay.tracker_events.iter()
+        .filter_map(|event| match event.kind { TrackerEventKind::StatGame(ref stat) => Some(stat), _ => None })
+        .filter(|stat| stat.name == name)
+        .collect()
+}
+
+#[test]
+fn repeated_keys_keep_every_value() {
+    let replay = common::parse_test_replay();
+    let deaths = stat_events(&replay, "PlayerDeath");
+    assert_eq!(deaths.len(), 37);
+
+    for death in deaths {
+        let victim = death.get_int("PlayerID").unwrap();
+        let killers = death.get_ints("KillingPlayer");
+        assert!(!killers.is_empty());
+        assert!(!killers.contains(&victim));
+        assert_eq!(death.get_int("KillingPlayer"), killers.first().cloned());
+
+        // Positions are fixed-point, and fall within the map.
+        let x = death.get_fixed("PositionX").unwrap();
+        let y = death.get_fixed("PositionY").unwrap();
+        assert!(x > 0.0 && x < replay.map_size.x as f64);
+        assert!(y > 0.0 && y < replay.map_size.y as f64);
+    }
+}
+
+#[test]
+fn fixed_point_values_are_decoded() {
+    let replay = common::parse_test_replay();
+    let breakdowns = stat_events(&replay, "PeriodicXPBreakdown");
+    assert!(!breakdowns.is_empty());
+
+    for breakdown in breakdowns {
+        let team = breakdown.get_int("Team").unwrap();
+        assert!(team == 1 || team == 2);
+
+        let game_time = breakdown.get_fixed("GameTime").unwrap();
+        let previous_game_time = breakdown.get_fixed("PreviousGameTime").unwrap();
+        assert!(game_time > previous_game_time);
+
+        assert_eq!(breakdown.get_string("Team"), None);
+        assert_eq!(breakdown.get_fixed("Missing"), None);
+        assert!(breakdown.get_ints("Missing").is_empty());
+    }
+}
@@ -0,0 +1,35 @@
+extern crate mpq;
+extern crate serde_json;
+extern crate storm_parser;
+
+mod common;
+
+use mpq::Archive;
+use storm_parser::{ReplayArchive, StreamArchive, StormParser};
+
+#[test]
+fn stream_archive_reads_the_same_files_as_mpq() {
+    let mut archive = Archive::open(common::TEST_REPLAY).expect("failed to open test replay");
+    let data = common::read_test_replay();
+    let mut stream_archive = StreamArchive::from_bytes(&data).expect("failed to open test replay from memory");
+
+    assert_eq!(ReplayArchive::read_user_data(&mut archive).unwrap(), stream_archive.read_user_data().unwrap());
+    for name in common::REPLAY_FILES.iter() {
+        let expected = ReplayArchive::read_file(&mut archive, name).unwrap();
+        let actual = stream_archive.read_file(name).unwrap();
+        assert!(expected == actual, "{} differs", name);
+    }
+}
+
+#[test]
+fn parse_replay_bytes_matches_parse_replay() {
+    let from_file = serde_json::to_value(&common::parse_test_replay()).unwrap();
+    let from_bytes = serde_json::to_value(&StormParser::parse_replay_bytes(common::read_test_replay()).unwrap()).unwrap();
+
+    let from_file = from_file.as_object().unwrap();
+    let from_bytes = from_bytes.as_object().unwrap();
+    assert_eq!(from_file.len(), from_bytes.len());
+    for (section, value) in from_file.iter() {
+        assert!(from_bytes.get(section) == Some(value), "{} differs", section);
+    }
+}
@@ -0,0 +1,42 @@
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::StructureKind;
+
+#[test]
+fn the_winners_destroy_the_core() {
+    let replay = common::parse_test_replay();
+    let winners = replay.players.iter().find(|player| player.is_winner).unwrap().team;
+
+    let cores = replay.structures.destroyed_by(winners, StructureKind::Core);
+    assert_eq!(cores.len(), 1);
+    assert!(replay.structures.destroyed_by(1 - winners, StructureKind::Core).is_empty());
+    assert_eq!(cores[0].ticks_elapsed, replay.structures.destroyed.last().unwrap().ticks_elapsed);
+}
+
+#[test]
+fn structures_fall_to_the_other_team() {
+    let replay = common::parse_test_replay();
+    let destroyed = &replay.structures.destroyed;
+    assert_eq!(destroyed.len(), 49);
+
+    for structure in destroyed.iter() {
+        assert!(structure.team.is_some());
+        assert!(structure.team != structure.destroying_team);
+    }
+    for pair in destroyed.windows(2) {
+        assert!(pair[0].ticks_elapsed <= pair[1].ticks_elapsed);
+    }
+
+    let firsts = [
+        (replay.structures.first_tower, StructureKind::Tower),
+        (replay.structures.first_fort, StructureKind::Fort),
+        (replay.structures.first_keep, StructureKind::Keep),
+    ];
+    for &(first, kind) in firsts.iter() {
+        let first = first.unwrap();
+        assert_eq!(destroyed[first].kind, kind);
+        assert!(destroyed[..first].iter().all(|structure| structure.kind != kind));
+    }
+}
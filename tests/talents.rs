@@ -0,0 +1,36 @@
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::{ParseOptions, StormParser};
+
+#[test]
+fn talents_are_reconciled_from_both_event_streams() {
+    let replay = common::parse_test_replay();
+
+    for player in replay.players.iter() {
+        assert!(!player.talents.is_empty());
+
+        for (i, talent) in player.talents.iter().enumerate() {
+            assert_eq!(talent.tier, i as u32 + 1);
+            assert!(talent.index.is_some());
+            assert!(talent.name.is_some());
+            assert_eq!(talent.time_sec, talent.ticks_elapsed / 16);
+        }
+
+        for pair in player.talents.windows(2) {
+            assert!(pair[0].ticks_elapsed <= pair[1].ticks_elapsed);
+        }
+    }
+}
+
+#[test]
+fn talents_are_named_from_the_tracker_events_alone() {
+    let options = ParseOptions::new().game_events(false);
+    let replay = StormParser::parse_replay_with_options(common::TEST_REPLAY, options).unwrap();
+
+    for player in replay.players.iter() {
+        assert!(!player.talents.is_empty());
+        assert!(player.talents.iter().all(|talent| talent.index.is_none() && talent.name.is_some()));
+    }
+}
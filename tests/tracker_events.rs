@@ -0,0 +1,45 @@
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::TrackerEventKind;
+
+#[test]
+fn tracker_events_are_typed() {
+    let replay = common::parse_test_replay();
+    assert_eq!(replay.tracker_events.len(), 6280);
+
+    // Everything in this replay is an event type we know the layout of.
+    for event in replay.tracker_events.iter() {
+        if let TrackerEventKind::Unknown { id, .. } = event.kind {
+            panic!("tracker event {} at tick {} wasn't decoded", id, event.ticks_elapsed);
+        }
+    }
+
+    // The events are in the order they happened.
+    for pair in replay.tracker_events.windows(2) {
+        assert!(pair[0].ticks_elapsed <= pair[1].ticks_elapsed);
+    }
+}
+
+#[test]
+fn heroes_are_born_for_every_player() {
+    let replay = common::parse_test_replay();
+
+    let setups: Vec<u32> = replay.tracker_events.iter()
+        .filter_map(|event| match event.kind { TrackerEventKind::PlayerSetup(ref setup) => Some(setup.player_id), _ => None })
+        .collect();
+    for player_id in 1..11 {
+        assert!(setups.contains(&player_id));
+    }
+
+    let heroes: Vec<u32> = replay.tracker_events.iter()
+        .filter_map(|event| match event.kind {
+            TrackerEventKind::UnitBorn(ref born) if born.unit_type_name.starts_with("Hero") => Some(born.control_player_id),
+            _ => None,
+        })
+        .collect();
+    for player_id in 1..11 {
+        assert!(heroes.contains(&player_id));
+    }
+}
@@ -0,0 +1,47 @@
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::TrackerEventKind;
+
+#[test]
+fn positions_are_in_map_units() {
+    let replay = common::parse_test_replay();
+    assert!(!replay.units.position_samples.is_empty());
+
+    for sample in replay.units.position_samples.iter() {
+        assert!(sample.x <= replay.map_size.x as u32);
+        assert!(sample.y <= replay.map_size.y as u32);
+    }
+}
+
+#[test]
+fn heroes_are_sampled_where_they_die() {
+    let replay = common::parse_test_replay();
+
+    // Positions are sampled every fifteen seconds, so only a death that lands right after a sample can be
+    // compared against it, and even then the hero has had a few ticks to move.
+    let mut compared = 0;
+    for event in replay.tracker_events.iter() {
+        let died = match event.kind {
+            TrackerEventKind::UnitDied(ref died) => died,
+            _ => continue,
+        };
+        if replay.player_identities.iter().all(|identity| identity.hero_unit_tag != Some(died.unit_tag())) {
+            continue
+        }
+
+        let sample = replay.units.position_samples_for(died.unit_tag()).into_iter()
+            .rev()
+            .find(|sample| sample.ticks_elapsed <= event.ticks_elapsed);
+        if let Some(sample) = sample {
+            if event.ticks_elapsed - sample.ticks_elapsed <= 16 {
+                assert!((sample.x as i64 - died.x as i64).abs() <= 4);
+                assert!((sample.y as i64 - died.y as i64).abs() <= 4);
+                compared += 1;
+            }
+        }
+    }
+
+    assert!(compared > 0);
+}
@@ -0,0 +1,39 @@
+extern crate storm_parser;
+
+mod common;
+
+#[test]
+fn heroes_are_tracked_through_their_deaths() {
+    let replay = common::parse_test_replay();
+    assert!(!replay.units.units.is_empty());
+
+    for (position, identity) in replay.player_identities.iter().enumerate() {
+        let hero = replay.units.get(identity.hero_unit_tag.unwrap()).expect("every hero is in the registry");
+        assert!(hero.unit_type_name().starts_with("Hero"));
+        assert!(hero.is_alive_at(hero.born_ticks_elapsed));
+
+        let deaths = replay.deaths.iter().filter(|death| death.victim == position).count();
+        assert_eq!(hero.deaths.len(), deaths);
+        assert!(hero.revivals.len() <= hero.deaths.len());
+
+        for death in hero.deaths.iter() {
+            assert!(!hero.is_alive_at(death.ticks_elapsed));
+        }
+
+        let state = replay.units.get_at(hero.unit_tag, hero.born_ticks_elapsed).unwrap();
+        assert_eq!(state.control_player_id, identity.tracker_player_id);
+    }
+}
+
+#[test]
+fn unit_histories_are_in_order() {
+    let replay = common::parse_test_replay();
+
+    for unit in replay.units.units.iter() {
+        assert_eq!(replay.units.get(unit.unit_tag).map(|found| found.unit_tag), Some(unit.unit_tag));
+        assert!(unit.types.windows(2).all(|pair| pair[0].ticks_elapsed <= pair[1].ticks_elapsed));
+        assert!(unit.owners.windows(2).all(|pair| pair[0].ticks_elapsed <= pair[1].ticks_elapsed));
+        assert!(unit.positions.windows(2).all(|pair| pair[0].ticks_elapsed <= pair[1].ticks_elapsed));
+        assert!(unit.deaths.iter().all(|death| death.ticks_elapsed >= unit.born_ticks_elapsed));
+    }
+}
@@ -0,0 +1,59 @@
+extern crate storm_parser;
+
+mod common;
+
+use storm_parser::{StormParser, TrackerEventKind};
+
+#[test]
+fn unknown_game_events_stop_the_stream() {
+    let expected = common::parse_test_replay().game_events.len();
+
+    // A global event of type 127, which doesn't exist, at the very end of the stream.
+    let mut archive = common::PatchedArchive::new()
+        .patch("replay.game.events", |mut data| { data.extend_from_slice(&[0x00, 0xF0, 0x0F]); data });
+    let replay = StormParser::parse_archive(&mut archive).unwrap();
+
+    assert_eq!(replay.game_events.len(), expected);
+    assert_eq!(replay.warnings.len(), 1);
+    assert!(replay.warnings[0].contains("unknown game event type '127'"));
+}
+
+#[test]
+fn unknown_tracker_events_are_kept() {
+    let expected = common::parse_test_replay().tracker_events.len();
+
+    // An event of type 50, which doesn't exist, holding the integer 5.
+    let mut archive = common::PatchedArchive::new()
+        .patch("replay.tracker.events", |mut data| {
+            data.extend_from_slice(&[0x03, 0x00, 0x09, 0x00, 0x09, 0x64, 0x09, 0x0A]);
+            data
+        });
+    let replay = StormParser::parse_archive(&mut archive).unwrap();
+
+    assert!(replay.warnings.is_empty());
+    assert_eq!(replay.tracker_events.len(), expected + 1);
+    match replay.tracker_events.last().unwrap().kind {
+        TrackerEventKind::Unknown { id, ref data } => {
+            assert_eq!(id, 50);
+            assert_eq!(data.get_vint().unwrap(), 5);
+        },
+        ref kind => panic!("expected an unknown event, got {:?}", kind),
+    }
+}
+
+#[test]
+fn unknown_tracker_data_types_stop_the_stream() {
+    let expected = common::parse_test_replay().tracker_events.len();
+
+    // An event whose data is of type 0x0B, which doesn't exist.
+    let mut archive = common::PatchedArchive::new()
+        .patch("replay.tracker.events", |mut data| {
+            data.extend_from_slice(&[0x03, 0x00, 0x09, 0x00, 0x09, 0x64, 0x0B]);
+            data
+        });
+    let replay = StormParser::parse_archive(&mut archive).unwrap();
+
+    assert_eq!(replay.tracker_events.len(), expected);
+    assert_eq!(replay.warnings.len(), 1);
+    assert!(replay.warnings[0].contains("unknown tracker event data type '11'"));
+}